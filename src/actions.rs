@@ -0,0 +1,409 @@
+//! Maps raw keyboard and gamepad input to game actions.
+//!
+//! Systems read [`ActionState`] instead of `Input<KeyCode>` so bindings can be
+//! changed at runtime and scripted input goes through the same path as
+//! devices. Bindings are stored in [`InputBindings::PATH`].
+
+use std::{collections::BTreeMap, io};
+
+use bevy::{input::InputSystem, prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{config, gamepad::stick_axis, player::Player, AppState};
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(InputBindings::load_or_default())
+            .init_resource::<ActionState>()
+            .add_systems(PreUpdate, update_action_state.after(InputSystem));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    /// Puts the ball into play when serving by hand.
+    Serve,
+    /// Short burst of speed in the direction the paddle is pushed.
+    Dash,
+    Pause,
+    Confirm,
+    Back,
+    /// Opens the rebinding screen from the main menu.
+    Controls,
+    ToggleInspector,
+    /// Watches the last recorded match from the main menu.
+    WatchReplay,
+    /// Pauses or resumes a replay.
+    PlaybackPause,
+    /// Advances a paused replay by a single tick.
+    PlaybackStep,
+    /// Plays a replay at four times the speed, or back at normal speed.
+    FastForward,
+    /// Plays a replay at a quarter of the speed, or back at normal speed.
+    SlowMotion,
+}
+
+impl Action {
+    /// Screens where the action does something, so that a key shared by two
+    /// actions only clashes when both are live at once. Outside of a match
+    /// Pause does what Back does, so it only counts where it pauses.
+    pub fn live_in(self) -> &'static [AppState] {
+        use AppState::*;
+
+        match self {
+            Action::MoveUp | Action::MoveDown => &[MainMenu, Game, Paused, GameOver, Controls],
+            Action::Serve | Action::Dash | Action::Pause => &[Game],
+            Action::Confirm => &[MainMenu, Paused, GameOver, Controls],
+            Action::Back => &[MainMenu, Paused, GameOver, Replay, Controls],
+            Action::Controls | Action::WatchReplay => &[MainMenu],
+            Action::ToggleInspector => &[MainMenu, Game, Paused, GameOver, Replay, Controls],
+            Action::PlaybackPause
+            | Action::PlaybackStep
+            | Action::FastForward
+            | Action::SlowMotion => &[Replay],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(GamepadButtonType),
+}
+
+impl Binding {
+    fn same_device(&self, other: &Binding) -> bool {
+        matches!(
+            (self, other),
+            (Binding::Key(_), Binding::Key(_)) | (Binding::Button(_), Binding::Button(_))
+        )
+    }
+}
+
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::Button(button) => write!(f, "Pad {button:?}"),
+        }
+    }
+}
+
+type ActionMap = BTreeMap<Action, Vec<Binding>>;
+
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct InputBindings {
+    /// Movement of the first and second player. Gamepad buttons here only
+    /// count on the gamepad assigned to that player.
+    pub players: [ActionMap; 2],
+    /// Everything else, from any keyboard or gamepad.
+    pub actions: ActionMap,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        use Binding::{Button, Key};
+        use GamepadButtonType::*;
+
+        let movement = |up: KeyCode, down: KeyCode, serve: KeyCode, dash: KeyCode| {
+            ActionMap::from([
+                (Action::MoveUp, vec![Key(up), Button(DPadUp)]),
+                (Action::MoveDown, vec![Key(down), Button(DPadDown)]),
+                (Action::Serve, vec![Key(serve), Button(South)]),
+                (Action::Dash, vec![Key(dash), Button(RightTrigger)]),
+            ])
+        };
+
+        Self {
+            players: [
+                movement(KeyCode::W, KeyCode::S, KeyCode::D, KeyCode::A),
+                movement(KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right),
+            ],
+            actions: ActionMap::from([
+                (Action::Pause, vec![Key(KeyCode::Escape), Button(Start)]),
+                (
+                    Action::Confirm,
+                    vec![
+                        Key(KeyCode::Return),
+                        Key(KeyCode::Space),
+                        Button(South),
+                        Button(Start),
+                    ],
+                ),
+                (
+                    Action::Back,
+                    vec![Key(KeyCode::Escape), Button(East), Button(Select)],
+                ),
+                (Action::Controls, vec![Key(KeyCode::C), Button(North)]),
+                (Action::ToggleInspector, vec![Key(KeyCode::Grave)]),
+                (Action::WatchReplay, vec![Key(KeyCode::R)]),
+                (
+                    Action::PlaybackPause,
+                    vec![Key(KeyCode::Space), Button(West)],
+                ),
+                (
+                    Action::PlaybackStep,
+                    vec![Key(KeyCode::Period), Button(RightTrigger)],
+                ),
+                (
+                    Action::FastForward,
+                    vec![Key(KeyCode::F), Button(RightTrigger2)],
+                ),
+                (
+                    Action::SlowMotion,
+                    vec![Key(KeyCode::Comma), Button(LeftTrigger2)],
+                ),
+            ]),
+        }
+    }
+}
+
+impl InputBindings {
+    pub const PATH: &'static str = "config/input.ron";
+
+    /// Reads the bindings file, falling back to (and writing out) the defaults.
+    /// Actions added since the file was written get their default bindings.
+    pub fn load_or_default() -> Self {
+        let mut bindings: Self = config::load_or_default(Self::PATH);
+
+        let defaults = Self::default();
+        let maps = bindings.players.iter_mut().chain([&mut bindings.actions]);
+        let default_maps = defaults.players.into_iter().chain([defaults.actions]);
+        for (map, default_map) in maps.zip(default_maps) {
+            for (action, default_bindings) in default_map {
+                map.entry(action).or_insert(default_bindings);
+            }
+        }
+
+        bindings
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        config::save(Self::PATH, self)
+    }
+
+    /// Bindings of `action`, for player `seat` or the shared ones.
+    pub fn get(&self, seat: Option<usize>, action: Action) -> &[Binding] {
+        let map = match seat {
+            Some(seat) => &self.players[seat],
+            None => &self.actions,
+        };
+        map.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replaces the bindings of `action` on the same device as `binding`.
+    pub fn rebind(&mut self, seat: Option<usize>, action: Action, binding: Binding) {
+        let map = match seat {
+            Some(seat) => &mut self.players[seat],
+            None => &mut self.actions,
+        };
+
+        let bindings = map.entry(action).or_default();
+        bindings.retain(|existing| !existing.same_device(&binding));
+        bindings.push(binding);
+    }
+
+    /// Every other action, by seat, that `binding` would also trigger on a
+    /// screen where `action` of `seat` is live. Gamepad buttons of different
+    /// players don't clash, as each only counts on that player's gamepad.
+    pub fn clashes(
+        &self,
+        seat: Option<usize>,
+        action: Action,
+        binding: Binding,
+    ) -> Vec<(Option<usize>, Action)> {
+        let mut clashes = Vec::new();
+        for (other_seat, map) in self.seats() {
+            let other_gamepad = matches!(binding, Binding::Button(_))
+                && seat.zip(other_seat).is_some_and(|(a, b)| a != b);
+            if other_gamepad {
+                continue;
+            }
+
+            for (&other, bindings) in map {
+                let live_together = other
+                    .live_in()
+                    .iter()
+                    .any(|state| action.live_in().contains(state));
+
+                if (other_seat, other) != (seat, action)
+                    && live_together
+                    && bindings.contains(&binding)
+                {
+                    clashes.push((other_seat, other));
+                }
+            }
+        }
+        clashes
+    }
+
+    /// Each player's bindings by seat, then the shared ones.
+    fn seats(&self) -> impl Iterator<Item = (Option<usize>, &ActionMap)> {
+        let players = self.players.iter().enumerate();
+        players
+            .map(|(seat, map)| (Some(seat), map))
+            .chain([(None, &self.actions)])
+    }
+}
+
+/// Which actions are held this frame, and how each player wants to move.
+#[derive(Resource, Default, Debug)]
+pub struct ActionState {
+    pressed: HashSet<Action>,
+    just_pressed: HashSet<Action>,
+    movement: [f32; 2],
+    serving: [bool; 2],
+    dashing: [bool; 2],
+    scripted: Vec<Action>,
+    scripted_movement: [Option<f32>; 2],
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    /// Movement of player `seat`, `-1.0` (down) to `1.0` (up).
+    pub fn movement(&self, seat: usize) -> f32 {
+        self.movement[seat]
+    }
+
+    /// Whether player `seat` holds their serve button.
+    pub fn serving(&self, seat: usize) -> bool {
+        self.serving[seat]
+    }
+
+    /// Whether player `seat` holds their dash button.
+    pub fn dashing(&self, seat: usize) -> bool {
+        self.dashing[seat]
+    }
+
+    /// Presses `action` for the next frame, as if a bound input was used.
+    pub fn press(&mut self, action: Action) {
+        self.scripted.push(action);
+    }
+
+    /// Overrides the movement of player `seat` for the next frame.
+    pub fn set_movement(&mut self, seat: usize, axis: f32) {
+        self.scripted_movement[seat] = Some(axis.clamp(-1.0, 1.0));
+    }
+}
+
+fn update_action_state(
+    bindings: Res<InputBindings>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    players: Query<&Player>,
+    mut state: ResMut<ActionState>,
+) {
+    let is_pressed = |binding: &Binding, pads: &[Gamepad]| match *binding {
+        Binding::Key(key) => keyboard_input.pressed(key),
+        Binding::Button(button) => pads
+            .iter()
+            .any(|&gamepad| gamepad_buttons.pressed(GamepadButton::new(gamepad, button))),
+    };
+    let held = |seat: Option<usize>, action: Action, pads: &[Gamepad]| {
+        bindings
+            .get(seat, action)
+            .iter()
+            .any(|binding| is_pressed(binding, pads))
+    };
+
+    let all_gamepads: Vec<_> = gamepads.iter().collect();
+    let mut seat_gamepads = [None; 2];
+    for player in &players {
+        for &seat in player.keys.seats() {
+            seat_gamepads[seat] = seat_gamepads[seat].or(player.gamepad);
+        }
+    }
+
+    let state = &mut *state;
+    let previous = std::mem::take(&mut state.pressed);
+
+    for &action in bindings.actions.keys() {
+        if held(None, action, &all_gamepads) {
+            state.pressed.insert(action);
+        }
+    }
+
+    for (seat, gamepad) in seat_gamepads.into_iter().enumerate() {
+        // menus can be navigated with either player's controls
+        for action in [Action::MoveUp, Action::MoveDown] {
+            if held(Some(seat), action, &all_gamepads) {
+                state.pressed.insert(action);
+            }
+        }
+
+        let pads: Vec<_> = gamepad.into_iter().collect();
+        let up = held(Some(seat), Action::MoveUp, &pads);
+        let down = held(Some(seat), Action::MoveDown, &pads);
+        let digital = up as i32 as f32 - down as i32 as f32;
+        state.serving[seat] = held(Some(seat), Action::Serve, &pads);
+        state.dashing[seat] = held(Some(seat), Action::Dash, &pads);
+        let analog = gamepad.map_or(0.0, |gamepad| stick_axis(gamepad, &gamepad_axes));
+
+        state.movement[seat] = match state.scripted_movement[seat].take() {
+            Some(scripted) => scripted,
+            None if digital != 0.0 => digital,
+            None => analog,
+        };
+    }
+
+    state.pressed.extend(state.scripted.drain(..));
+    state.just_pressed = state.pressed.difference(&previous).copied().collect();
+}
+
+/// Run condition that flips every time `action` is pressed, like
+/// `input_toggle_active` for raw keys.
+pub fn action_toggle_active(
+    default: bool,
+    action: Action,
+) -> impl FnMut(Res<ActionState>) -> bool + Clone {
+    let mut active = default;
+    move |actions: Res<ActionState>| {
+        active ^= actions.just_pressed(action);
+        active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings_do_not_clash() {
+        let bindings = InputBindings::default();
+
+        for (seat, map) in bindings.seats() {
+            for (&action, action_bindings) in map {
+                for &binding in action_bindings {
+                    let clashes = bindings.clashes(seat, action, binding);
+                    assert!(
+                        clashes.is_empty(),
+                        "{binding} of {action:?} also triggers {clashes:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_key_shared_by_live_actions_clashes() {
+        let mut bindings = InputBindings::default();
+        bindings.rebind(None, Action::Pause, Binding::Key(KeyCode::S));
+        assert_eq!(
+            bindings.clashes(None, Action::Pause, Binding::Key(KeyCode::S)),
+            [(Some(0), Action::MoveDown)]
+        );
+    }
+}
@@ -1,70 +1,499 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::{prelude::ReflectInspectorOptions, InspectorOptions};
-use bevy_magic_light_2d::prelude::{LightOccluder2D, OmniLightSource2D};
 
-use crate::{ball::Ball, AppState, Paddle, Score};
+use crate::{
+    controller::{AppControllerExt, ControlContext, PaddleController},
+    game::{GameSet, MatchScore, Paddle, Simulation, SimulationEvent},
+    rng::{GameRng, Rng},
+    settings::Settings,
+    sim::{BallId, BallState, PaddleId, PaddleInput, PaddleState, PongSim, Side, SimEvent},
+    AppState,
+};
+
+/// Bounds of [`AI::handicap`] under adaptive difficulty.
+const MIN_HANDICAP: f32 = 0.5;
+const MAX_HANDICAP: f32 = 2.0;
+/// Handicap added per point the computer leads by.
+const LEAD_WEIGHT: f32 = 0.15;
+/// Fraction of the way the handicap moves towards its target after a point.
+const SMOOTHING: f32 = 0.5;
+/// Rallies this long mean the players are already even.
+const LONG_RALLY: u32 = 8;
+/// However the handicap swings, the computer never reacts faster or guesses
+/// better than this, nor slower or worse than the upper bounds.
+const REACTION_BOUNDS: (f32, f32) = (0.05, 0.5);
+const ERROR_BOUNDS: (f32, f32) = (2.0, 24.0);
 
 pub struct AiPlugin;
 
 impl Plugin for AiPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<AI>()
-            .add_systems(Startup, setup)
-            .add_systems(Update, ai_controller.run_if(in_state(AppState::Game)));
-    }
-}
-
-fn setup(mut commands: Commands) {
-    // Create the right paddle
-    commands.spawn((
-        Name::new("Right Paddle"),
-        Paddle { speed: 50.0 },
-        AI,
-        Score::default(),
-        SpriteBundle {
-            transform: Transform::from_translation(Vec3::new(100.0, 0.0, 0.0)),
-            sprite: Sprite {
-                color: Color::WHITE,
-                custom_size: Some(Vec2::new(4.0, 16.0)),
-                anchor: default(),
-                ..default()
-            },
-            ..Default::default()
-        },
-        OmniLightSource2D {
-            intensity: 0.2,
-            color: Color::rgb_u8(255, 28, 28),
-            falloff: Vec3::new(0.15, 0.25, 0.005),
-            ..default()
-        },
-        LightOccluder2D {
-            h_size: Vec2::new(4.0, 16.0),
-        },
-    ));
-}
-
-fn ai_controller(
-    mut query: Query<(&Paddle, &mut Transform), With<AI>>,
-    ball_query: Query<(&Ball, &Transform), Without<AI>>,
-    time: Res<Time>,
+            .init_resource::<Difficulty>()
+            .add_paddle_controller::<AI>()
+            .add_systems(
+                FixedUpdate,
+                apply_difficulty_speed
+                    .in_set(GameSet::Input)
+                    .run_if(in_state(AppState::Game).or_else(in_state(AppState::MainMenu))),
+            )
+            .add_systems(
+                Update,
+                adapt_difficulty
+                    .after(GameSet::Sync)
+                    .run_if(in_state(AppState::Game).and_then(adaptive_ai_enabled)),
+            );
+    }
+}
+
+/// Holds every computer paddle to the top speed of its difficulty, so picking
+/// another difficulty mid-match changes how fast it moves as well as how well
+/// it reads the ball.
+fn apply_difficulty_speed(
+    mut simulation: ResMut<Simulation>,
+    mut ais: Query<(&PaddleId, &AI, &mut Paddle)>,
 ) {
-    let ball = ball_query.single();
-    let ball_transform = ball.1;
+    for (id, ai, mut paddle) in &mut ais {
+        let speed = ai.difficulty.params().max_speed;
+        if paddle.speed != speed {
+            paddle.speed = speed;
+        }
+        if let Some(state) = simulation.sim.paddles.get_mut(id.index()) {
+            state.speed = speed;
+        }
+    }
+}
 
-    for (paddle, mut transform) in &mut query {
-        let speed = paddle.speed * time.delta_seconds();
+fn adaptive_ai_enabled(settings: Res<Settings>) -> bool {
+    settings.adaptive_ai
+}
 
-        if ball_transform.translation.y > transform.translation.y && transform.translation.y < 64.0
-        {
-            transform.translation.y += speed;
+/// After every point, eases the computer off while it is ahead and tightens
+/// it up while it is behind, less so after long rallies.
+fn adapt_difficulty(
+    mut sim_events: EventReader<SimulationEvent>,
+    simulation: Res<Simulation>,
+    score: Res<MatchScore>,
+    mut ais: Query<(&Side, &mut AI)>,
+) {
+    let points = sim_events
+        .iter()
+        .filter(|event| matches!(event.0, SimEvent::Goal { .. }))
+        .count();
+    if points == 0 {
+        return;
+    }
+
+    let rally = simulation.sim.stats.last_rally;
+
+    for (side, mut ai) in &mut ais {
+        let lead = score.lead(*side);
+
+        let mut target = 1.0 + LEAD_WEIGHT * lead as f32;
+        if rally >= LONG_RALLY {
+            target = (target + 1.0) / 2.0;
+        }
+
+        let previous = ai.handicap.unwrap_or(1.0);
+        let handicap =
+            (previous + (target - previous) * SMOOTHING).clamp(MIN_HANDICAP, MAX_HANDICAP);
+        ai.handicap = Some(handicap);
+        info!("Adaptive AI: handicap {previous:.2} -> {handicap:.2} (lead {lead}, rally {rally})");
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Impossible,
+}
+
+impl AiParams {
+    /// Slower reactions and worse guesses for a `handicap` above `1.0`,
+    /// sharper ones below, always within the adaptive bounds. Presets outside
+    /// of them are pulled in too, so even Impossible gets beatable once it
+    /// adapts.
+    pub fn handicapped(self, handicap: f32) -> Self {
+        let scale = |value: f32, (min, max): (f32, f32)| (value * handicap).clamp(min, max);
+
+        Self {
+            reaction_delay: scale(self.reaction_delay, REACTION_BOUNDS),
+            prediction_error: scale(self.prediction_error, ERROR_BOUNDS),
+            ..self
+        }
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Impossible,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Impossible => "Impossible",
         }
-        if ball_transform.translation.y < transform.translation.y && transform.translation.y > -64.0
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The next harder difficulty, wrapping around to the easiest.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    pub fn params(self) -> AiParams {
+        match self {
+            Difficulty::Easy => AiParams {
+                reaction_delay: 0.4,
+                prediction_error: 24.0,
+                max_speed: 45.0,
+                aim: 0.0,
+            },
+            Difficulty::Normal => AiParams {
+                reaction_delay: 0.25,
+                prediction_error: 14.0,
+                max_speed: 60.0,
+                aim: 0.25,
+            },
+            Difficulty::Hard => AiParams {
+                reaction_delay: 0.12,
+                prediction_error: 6.0,
+                max_speed: 80.0,
+                aim: 0.5,
+            },
+            Difficulty::Impossible => AiParams {
+                reaction_delay: 0.0,
+                prediction_error: 0.0,
+                max_speed: 240.0,
+                aim: 0.8,
+            },
+        }
+    }
+}
+
+/// How well the computer plays.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AiParams {
+    /// Seconds before it starts moving towards a ball heading its way.
+    pub reaction_delay: f32,
+    /// Largest miss, in world units, of its guess where the ball will arrive.
+    pub prediction_error: f32,
+    /// Paddle speed, kept up to date every tick while it plays.
+    pub max_speed: f32,
+    /// How far towards the paddle edge it tries to take the ball, `0.0` (always
+    /// the center) to `1.0` (the very edge), to angle returns away from the
+    /// opponent.
+    pub aim: f32,
+}
+
+/// Per-paddle memory of the computer player between ticks.
+#[derive(Clone, Debug)]
+pub struct AiBrain {
+    /// The ball it last had to deal with; a change means a new shot.
+    ball: Option<BallId>,
+    /// Seconds left before reacting to an incoming ball.
+    reaction: f32,
+    /// Height the paddle is moving to, once decided.
+    target: Option<f32>,
+    /// Leaving the incoming ball to a teammate and holding its own ground.
+    covering: bool,
+    /// Source of its misjudgements.
+    rng: Rng,
+}
+
+impl AiBrain {
+    pub fn new(rng: Rng) -> Self {
+        Self {
+            ball: None,
+            reaction: 0.0,
+            target: None,
+            covering: false,
+            rng,
+        }
+    }
+
+    /// Decides the movement of paddle `id` for the next tick.
+    pub fn think(
+        &mut self,
+        sim: &PongSim,
+        id: PaddleId,
+        params: &AiParams,
+        dt: f32,
+    ) -> PaddleInput {
+        let paddle = sim.paddle(id);
+
+        if let Some(serve) = &sim.serve {
+            if PaddleId::from(serve.server) == id && serve.countdown <= 0.0 {
+                // back home first, then serve aimed a little off straight
+                if (paddle.position.y - paddle.home()).abs() > 1.0 {
+                    return steer(paddle, paddle.home(), dt);
+                }
+                return PaddleInput::new(self.noise() * params.aim).serving(true);
+            }
+        }
+
+        // a caught ball goes straight back, aimed like a serve
+        if sim
+            .balls
+            .iter()
+            .any(|ball| matches!(ball.held, Some(hold) if hold.paddle == id))
         {
-            transform.translation.y -= speed;
+            return PaddleInput::new(self.noise() * params.aim).serving(true);
+        }
+
+        let ball = threat(sim, id.side);
+
+        match ball {
+            // wait for the next shot at home
+            None => self.target = Some(paddle.home()),
+            Some(ball) if Some(ball.id) != self.ball => {
+                self.reaction = params.reaction_delay;
+                self.target = None;
+                // teammates all decide on the tick of the shot, so exactly
+                // one of them goes for it
+                self.covering = !takes_shot(sim, ball, id);
+            }
+            Some(ball) if self.covering && !teammate_in_play(sim, ball, id) => {
+                // the ball got past whoever was taking it
+                self.covering = false;
+                self.target = None;
+            }
+            Some(_) => {}
+        }
+        self.ball = ball.map(|ball| ball.id);
+
+        if let (Some(ball), None) = (ball, self.target) {
+            self.reaction -= dt;
+            if self.reaction <= 0.0 {
+                self.target = if self.covering {
+                    Some(paddle.home())
+                } else {
+                    predict_intercept(sim, ball, id).map(|(_, intercept)| {
+                        let error = self.noise() * params.prediction_error;
+                        intercept + error + aim_offset(sim, id, intercept, params.aim)
+                    })
+                };
+            }
         }
+
+        match self.target {
+            Some(target) => steer(paddle, target, dt),
+            None => PaddleInput::IDLE,
+        }
+    }
+
+    /// `-1.0` to `1.0`.
+    fn noise(&mut self) -> f32 {
+        self.rng.range(-1.0, 1.0)
+    }
+}
+
+/// The ball heading for `side`'s goal that gets there first, so the one its
+/// paddles have to deal with before any other; `None` while every ball is
+/// heading the other way.
+pub fn threat(sim: &PongSim, side: Side) -> Option<&BallState> {
+    let goal = -side.forward() * sim.config.goal_x;
+    let arrival = |ball: &BallState| (goal - ball.position.x) / ball.velocity.x;
+
+    sim.balls
+        .iter()
+        .filter(|ball| ball.velocity.x * side.forward() < 0.0)
+        .min_by(|a, b| arrival(a).total_cmp(&arrival(b)))
+}
+
+/// Seconds until `ball` reaches paddle `id`, and the height it gets there
+/// at, following its bounces off the walls; `None` while it is heading the
+/// other way. Spin is left out, so a curving ball fools it.
+pub fn predict_intercept(sim: &PongSim, ball: &BallState, id: PaddleId) -> Option<(f32, f32)> {
+    let config = &sim.config;
+
+    if ball.velocity.x * id.side.forward() >= 0.0 {
+        return None;
+    }
+
+    let time = ((face_x(sim, id) - ball.position.x) / ball.velocity.x).max(0.0);
+    let y = ball.position.y + ball.velocity.y * time;
+
+    // bouncing between the walls folds the straight path back and forth
+    let limit = config.wall_y - config.ball_size.y / 2.0;
+    let span = 2.0 * limit;
+    let folded = (y + limit).rem_euclid(2.0 * span);
+    let folded = if folded > span {
+        2.0 * span - folded
+    } else {
+        folded
+    };
+
+    Some((time, folded - limit))
+}
+
+/// Where the ball's center is when it touches the face of paddle `id`.
+fn face_x(sim: &PongSim, id: PaddleId) -> f32 {
+    let paddle = sim.paddle(id);
+    paddle.position.x + id.side.forward() * (sim.config.ball_size.x + paddle.size.x) / 2.0
+}
+
+/// Whether paddle `id` should go for the incoming `ball` rather than leave
+/// it to a teammate. Of the paddles the ball has yet to get past, the first
+/// it reaches that can make it there in time takes it, or failing that
+/// whichever would be the least late.
+pub fn takes_shot(sim: &PongSim, ball: &BallState, id: PaddleId) -> bool {
+    let chances: Vec<_> = sim
+        .paddle_ids()
+        .filter(|&other| other.side == id.side && ahead_of_ball(sim, ball, other))
+        .filter_map(|other| {
+            let (time, intercept) = predict_intercept(sim, ball, other)?;
+            Some((other, time, lateness(sim, other, time, intercept)))
+        })
+        .collect();
+
+    let in_time = chances
+        .iter()
+        .filter(|(_, _, late)| *late <= 0.0)
+        .min_by(|(_, a, _), (_, b, _)| a.total_cmp(b));
+    let taker = in_time.or_else(|| chances.iter().min_by(|(_, _, a), (_, _, b)| a.total_cmp(b)));
+
+    !matches!(taker, Some((taker, _, _)) if *taker != id)
+}
+
+/// Seconds paddle `id` would arrive after a ball due at `intercept` in
+/// `time`, negative with time to spare and infinite when out of its zone.
+fn lateness(sim: &PongSim, id: PaddleId, time: f32, intercept: f32) -> f32 {
+    let paddle = sim.paddle(id);
+    let reach = paddle.size.y / 2.0;
+    let (low, high) = paddle.zone;
+
+    if paddle.top_speed() <= 0.0 || intercept < low - reach || intercept > high + reach {
+        return f32::INFINITY;
+    }
+    (intercept.clamp(low, high) - paddle.position.y).abs() / paddle.top_speed() - time
+}
+
+/// Whether `ball` still has to get past paddle `id` on its way to goal.
+fn ahead_of_ball(sim: &PongSim, ball: &BallState, id: PaddleId) -> bool {
+    (ball.position.x - face_x(sim, id)) * id.side.forward() > 0.0
+}
+
+/// Whether `ball` has yet to get past some teammate of `id`.
+fn teammate_in_play(sim: &PongSim, ball: &BallState, id: PaddleId) -> bool {
+    sim.paddle_ids()
+        .any(|other| other.side == id.side && other != id && ahead_of_ball(sim, ball, other))
+}
+
+/// Where to hold the paddle relative to `intercept` so the ball comes off
+/// the paddle angled away from the opponent.
+fn aim_offset(sim: &PongSim, id: PaddleId, intercept: f32, aim: f32) -> f32 {
+    let opponent = sim.paddle(id.side.opponent()).position.y;
+    // a ball taken below the center goes down, and the other way around
+    let direction = if opponent > intercept { 1.0 } else { -1.0 };
+    direction * aim.clamp(0.0, 1.0) * sim.paddle(id).size.y / 2.0
+}
+
+/// Input that moves `paddle` to `target` without overshooting it.
+fn steer(paddle: &PaddleState, target: f32, dt: f32) -> PaddleInput {
+    if paddle.top_speed() <= 0.0 || dt <= 0.0 {
+        return PaddleInput::IDLE;
     }
+
+    let gap = target - paddle.position.y;
+    let mut speed = gap.abs() / dt;
+    let acceleration = paddle.motion.acceleration;
+    if acceleration > 0.0 {
+        // a heavy paddle has to start braking early to stop on the target
+        speed = speed.min((2.0 * acceleration * gap.abs()).sqrt());
+    }
+    PaddleInput::new(gap.signum() * speed / paddle.top_speed())
+}
+
+/// A computer-controlled paddle.
+#[derive(Component, InspectorOptions, Reflect)]
+#[reflect(Component, InspectorOptions, from_reflect = false)]
+pub struct AI {
+    pub difficulty: Difficulty,
+    /// Scales reaction time and prediction error of the difficulty preset
+    /// with adaptive difficulty on; `None` plays the preset as it is.
+    pub handicap: Option<f32>,
+    #[reflect(ignore)]
+    brain: AiBrain,
 }
 
-#[derive(Component, InspectorOptions, Default, Reflect)]
-#[reflect(Component, InspectorOptions)]
-pub struct AI;
+/// Added through reflection, such as from the inspector, it plays at the
+/// chosen difficulty and still draws from the game's seeded randomness.
+impl FromWorld for AI {
+    fn from_world(world: &mut World) -> Self {
+        let difficulty = *world.resource::<Difficulty>();
+        let rng = world.resource_mut::<GameRng>().fork();
+        Self::new(difficulty, rng)
+    }
+}
+
+impl AI {
+    pub fn new(difficulty: Difficulty, rng: Rng) -> Self {
+        Self {
+            difficulty,
+            handicap: None,
+            brain: AiBrain::new(rng),
+        }
+    }
+
+    /// Adapts to the score from the first point when `adaptive` is set.
+    pub fn adaptive(self, adaptive: bool) -> Self {
+        Self {
+            handicap: adaptive.then_some(1.0),
+            ..self
+        }
+    }
+
+    pub fn params(&self) -> AiParams {
+        let params = self.difficulty.params();
+        match self.handicap {
+            Some(handicap) => params.handicapped(handicap),
+            None => params,
+        }
+    }
+}
+
+impl PaddleController for AI {
+    fn next_input(&mut self, ctx: &ControlContext) -> PaddleInput {
+        let params = self.params();
+        self.brain.think(ctx.sim, ctx.paddle, &params, ctx.dt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handicapped_params_stay_within_the_adaptive_bounds() {
+        let within = |value: f32, (min, max): (f32, f32)| (min..=max).contains(&value);
+
+        for difficulty in Difficulty::ALL {
+            for handicap in [0.0, MIN_HANDICAP, 1.0, MAX_HANDICAP, 100.0] {
+                let params = difficulty.params().handicapped(handicap);
+                assert!(
+                    within(params.reaction_delay, REACTION_BOUNDS),
+                    "{difficulty:?} at {handicap}: {params:?}"
+                );
+                assert!(
+                    within(params.prediction_error, ERROR_BOUNDS),
+                    "{difficulty:?} at {handicap}: {params:?}"
+                );
+            }
+        }
+    }
+}
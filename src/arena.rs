@@ -0,0 +1,132 @@
+//! Size and shape of the court.
+//!
+//! [`Arena`] is the one place court geometry is defined. The simulation's
+//! walls, goals and paddle travel come from [`SimConfig::new`], and the
+//! sprites, center line and camera are fitted to it whenever it changes.
+//! Courts come from one of the built-in [`Arena::presets`] or a RON file,
+//! picked with `--arena <preset or file>`.
+
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::Side;
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Arena {
+    pub name: String,
+    /// Playing field between the goal lines and between the walls.
+    pub size: Vec2,
+    pub ball_size: Vec2,
+    pub paddle_size: Vec2,
+    /// Distance of each paddle in front of its goal line.
+    pub paddle_inset: f32,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl Arena {
+    pub fn standard() -> Self {
+        Self {
+            name: "Standard".into(),
+            size: Vec2::new(256.0, 144.0),
+            ball_size: Vec2::new(4.0, 4.0),
+            paddle_size: Vec2::new(4.0, 16.0),
+            paddle_inset: 28.0,
+        }
+    }
+
+    pub fn wide() -> Self {
+        Self {
+            name: "Wide".into(),
+            size: Vec2::new(352.0, 144.0),
+            ..Self::standard()
+        }
+    }
+
+    pub fn narrow() -> Self {
+        Self {
+            name: "Narrow".into(),
+            size: Vec2::new(192.0, 144.0),
+            paddle_inset: 20.0,
+            ..Self::standard()
+        }
+    }
+
+    pub fn tall() -> Self {
+        Self {
+            name: "Tall".into(),
+            size: Vec2::new(256.0, 208.0),
+            paddle_size: Vec2::new(4.0, 20.0),
+            ..Self::standard()
+        }
+    }
+
+    pub fn presets() -> [Arena; 4] {
+        [Self::standard(), Self::wide(), Self::narrow(), Self::tall()]
+    }
+
+    /// A preset by (case-insensitive) name, otherwise a RON file at `arg`.
+    pub fn from_arg(arg: &str) -> io::Result<Self> {
+        match Self::presets()
+            .into_iter()
+            .find(|arena| arena.name.eq_ignore_ascii_case(arg))
+        {
+            Some(arena) => Ok(arena),
+            None => Self::load(arg),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// The preset after this one, wrapping around; custom courts go back to
+    /// the first preset.
+    pub fn next_preset(&self) -> Self {
+        let presets = Self::presets();
+        let next = presets
+            .iter()
+            .position(|arena| arena == self)
+            .map_or(0, |index| (index + 1) % presets.len());
+        presets[next].clone()
+    }
+
+    /// Distance of each goal line from the center line.
+    pub fn goal_x(&self) -> f32 {
+        self.size.x / 2.0
+    }
+
+    /// Height of the top and bottom walls.
+    pub fn wall_y(&self) -> f32 {
+        self.size.y / 2.0
+    }
+
+    /// Distance of each paddle from the center line.
+    pub fn paddle_x(&self) -> f32 {
+        self.goal_x() - self.paddle_inset
+    }
+
+    /// Distance of the front paddles from the center line in doubles,
+    /// halfway between it and the back paddles.
+    pub fn front_paddle_x(&self) -> f32 {
+        self.paddle_x() / 2.0
+    }
+
+    /// How far a paddle center may travel up or down before touching a wall.
+    pub fn paddle_limit(&self) -> f32 {
+        self.wall_y() - self.paddle_size.y / 2.0
+    }
+
+    /// Where the paddle on `side` starts out.
+    pub fn paddle_start(&self, side: Side) -> Vec2 {
+        Vec2::new(-side.forward() * self.paddle_x(), 0.0)
+    }
+}
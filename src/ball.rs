@@ -1,22 +1,28 @@
-use bevy::sprite::collide_aabb::collide;
-use bevy::{prelude::*, sprite::collide_aabb::Collision};
+use bevy::prelude::*;
 use bevy_inspector_egui::{prelude::ReflectInspectorOptions, InspectorOptions};
 use bevy_magic_light_2d::prelude::*;
 
+use crate::arena::Arena;
+use crate::game::{interpolation_alpha, GameSet, Simulation, SimulationEvent, StateScoped};
 use crate::particle::SpawnParticle;
-use crate::{AppState, Paddle};
+use crate::sim::{BallId, BallState, PaddleId, Side, SimEvent};
+use crate::Paddle;
+
+/// How fast the ball sprite turns per unit of spin, in radians per second.
+const SPIN_ROTATION_RATE: f32 = 12.0;
+/// How far a hit's particle spray leans per unit of spin, in radians.
+const SPIN_PARTICLE_TILT: f32 = 0.5;
 
 pub struct BallPlugin;
 
 impl Plugin for BallPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<Ball>()
-            .add_systems(Startup, setup)
-            .add_systems(
-                Update,
-                (move_ball, ball_paddle_collision, ball_bounds_collision)
-                    .run_if(in_state(AppState::Game)),
-            );
+        app.register_type::<Ball>().add_systems(
+            Update,
+            (match_balls, sync_ball, ball_paddle_collision)
+                .chain()
+                .in_set(GameSet::Sync),
+        );
     }
 }
 
@@ -24,20 +30,20 @@ impl Plugin for BallPlugin {
 #[reflect(Component, InspectorOptions)]
 pub struct Ball {
     pub velocity: Vec2,
+    pub spin: f32,
 }
 
-fn setup(mut commands: Commands) {
-    // create the ball, center screen 4x4px
-    commands.spawn((
+/// A ball showing `ball` from the simulation, lit in `color`.
+pub fn ball_bundle(arena: &Arena, ball: &BallState, color: Color) -> impl Bundle {
+    (
         Name::new("Ball"),
-        Ball {
-            velocity: Vec2::new(-50.0, 0.0),
-        },
+        Ball::default(),
+        ball.id,
         SpriteBundle {
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
+            transform: Transform::from_translation(ball.position.extend(0.0)),
             sprite: Sprite {
                 color: Color::WHITE,
-                custom_size: Some(Vec2::new(4.0, 4.0)),
+                custom_size: Some(arena.ball_size),
                 anchor: default(),
                 ..default()
             },
@@ -45,109 +51,111 @@ fn setup(mut commands: Commands) {
         },
         OmniLightSource2D {
             intensity: 0.05,
-            color: Color::rgb_u8(255, 255, 255), // randomize on each hit
+            color, // takes the color of the last paddle hit
             falloff: Vec3::new(0.15, 0.25, 0.005),
             ..default()
         },
         LightOccluder2D {
-            h_size: Vec2::new(4.0, 4.0),
+            h_size: arena.ball_size,
         },
-    ));
+    )
 }
 
-fn move_ball(mut query: Query<(&Ball, &mut Transform)>, time: Res<Time>) {
-    for (ball, mut transform) in &mut query {
-        transform.translation += ball.velocity.extend(0.0) * time.delta_seconds();
+/// Keeps one entity per ball in play: spawns the balls a split adds, lit
+/// like the paddle that hit them, and despawns the ones that scored. New
+/// balls join the court the existing ones belong to.
+fn match_balls(
+    mut commands: Commands,
+    simulation: Res<Simulation>,
+    arena: Res<Arena>,
+    balls: Query<(Entity, &BallId, &StateScoped), With<Ball>>,
+    paddles: Query<(&PaddleId, &OmniLightSource2D), With<Paddle>>,
+) {
+    let Some((_, _, &scope)) = balls.iter().next() else {
+        return;
+    };
+
+    for (entity, id, _) in &balls {
+        if simulation.sim.ball(*id).is_none() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for ball in &simulation.sim.balls {
+        if balls.iter().any(|(_, id, _)| *id == ball.id) {
+            continue;
+        }
+        let color = ball
+            .last_hit
+            .and_then(|hit| paddles.iter().find(|(id, _)| **id == hit))
+            .map_or(Color::WHITE, |(_, light)| light.color);
+        commands.spawn((ball_bundle(&arena, ball, color), scope));
+    }
+}
+
+fn sync_ball(
+    simulation: Res<Simulation>,
+    fixed_time: Res<FixedTime>,
+    time: Res<Time>,
+    mut query: Query<(&BallId, &mut Ball, &mut Transform)>,
+) {
+    let alpha = interpolation_alpha(&fixed_time);
+
+    for (id, mut ball, mut transform) in &mut query {
+        let (Some(state), Some(position)) = (
+            simulation.sim.ball(*id),
+            simulation.ball_position(*id, alpha),
+        ) else {
+            continue;
+        };
+        ball.velocity = state.velocity;
+        ball.spin = state.spin;
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+
+        // a spinning ball visibly spins, a plain one settles back square
+        if ball.spin == 0.0 {
+            transform.rotation = Quat::IDENTITY;
+        } else {
+            transform.rotate_z(ball.spin * SPIN_ROTATION_RATE * time.delta_seconds());
+        }
     }
 }
 
 fn ball_paddle_collision(
-    mut ball_query: Query<(&mut Ball, &mut Transform, &mut OmniLightSource2D), Without<Paddle>>,
-    paddle_query: Query<(&OmniLightSource2D, &Transform), With<Paddle>>,
+    mut sim_events: EventReader<SimulationEvent>,
+    mut ball_query: Query<(&BallId, &mut OmniLightSource2D), (With<Ball>, Without<Paddle>)>,
+    paddle_query: Query<(&Side, &OmniLightSource2D), With<Paddle>>,
     mut particle_event: EventWriter<SpawnParticle>,
 ) {
-    let mut ball = ball_query.single_mut();
-    let mut ball_transform = ball.1;
-    let mut ball_light = ball.2;
-
-    for (paddle_light, paddle_transform) in &paddle_query {
-        let paddle_size = Vec2::new(4.0, 16.0);
-        let ball_size = Vec2::new(4.0, 4.0);
-
-        let Some(collision) = collide(
-            paddle_transform.translation,
-            paddle_size,
-            ball_transform.translation,
-            ball_size,
-        ) else {
+    for event in sim_events.iter() {
+        let SimEvent::PaddleHit {
+            ball,
+            side,
+            contact,
+            velocity,
+            spin,
+            ..
+        } = event.0
+        else {
             continue;
         };
 
-        let offset = ball_transform.translation.y - paddle_transform.translation.y;
-        let adjustment = offset / (paddle_size.y / 2.0);
+        let particle_rotation = Quat::from_rotation_z(velocity.y.atan2(velocity.x))
+            * -Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)
+            * Quat::from_rotation_z(std::f32::consts::PI)
+            * Quat::from_rotation_z(spin * SPIN_PARTICLE_TILT);
 
-        match collision {
-            // top
-            Collision::Top => {
-                ball_transform.translation.y =
-                    paddle_transform.translation.y - paddle_size.y / 2.0 + ball_size.y / 2.0;
-                ball.0.velocity.y *= -1.0;
-            }
-            // bottom
-            Collision::Bottom => {
-                ball_transform.translation.y =
-                    paddle_transform.translation.y + paddle_size.y / 2.0 - ball_size.y / 2.0;
-                ball.0.velocity.y *= -1.0;
+        // each ball takes the color of the paddle that last hit it
+        if let Some((_, paddle_light)) = paddle_query.iter().find(|(paddle, _)| **paddle == side) {
+            for (_, mut ball_light) in ball_query.iter_mut().filter(|(id, _)| **id == ball) {
+                ball_light.color = paddle_light.color;
             }
-            // left
-            Collision::Left => {
-                ball_transform.translation.x =
-                    paddle_transform.translation.x + paddle_size.x / 2.0 + ball_size.x / 2.0;
-
-                ball.0.velocity.x *= -1.1;
-                ball.0.velocity.x = ball.0.velocity.x.clamp(-100.0, 100.0);
-                ball.0.velocity.y += adjustment * 50.0;
-            }
-            // right
-            Collision::Right => {
-                ball_transform.translation.x =
-                    paddle_transform.translation.x - paddle_size.x / 2.0 - ball_size.x / 2.0;
-
-                ball.0.velocity.x *= -1.1;
-                ball.0.velocity.x = ball.0.velocity.x.clamp(-100.0, 100.0);
-                ball.0.velocity.y += adjustment * 50.0;
-            }
-            Collision::Inside => {}
         }
 
-        let particle_rotation = Quat::from_rotation_z(ball.0.velocity.y.atan2(ball.0.velocity.x))
-            * -Quat::from_rotation_z(std::f32::consts::FRAC_PI_2)
-            * Quat::from_rotation_z(std::f32::consts::PI);
-
-        // particle position is in between the ball and the paddle, calculate
-        let particle_position = ball_transform.translation
-            + (paddle_transform.translation - ball_transform.translation) / 2.0;
-
-        ball_light.color = paddle_light.color;
         particle_event.send(SpawnParticle {
-            position: particle_position,
+            position: contact.extend(0.0),
             rotation: particle_rotation,
         });
     }
 }
-
-fn ball_bounds_collision(mut ball_query: Query<(&mut Ball, &Transform)>) {
-    // y bounds, -64 ~ 64
-
-    let mut ball = ball_query.single_mut();
-    let ball_transform = ball.1;
-
-    let ball_size = Vec2::new(4.0, 4.0);
-
-    let ball_top = ball_transform.translation.y + ball_size.y / 2.0;
-    let ball_bottom = ball_transform.translation.y - ball_size.y / 2.0;
-
-    if ball_top > 72.0 || ball_bottom < -72.0 {
-        ball.0.velocity.y *= -1.0;
-    }
-}
@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::Difficulty,
+    arena::Arena,
+    game::{GamePlugin, DEFAULT_TICK_RATE},
+    rng::GameRng,
+    rules::MatchRules,
+};
+
+/// Options passed on the command line.
+#[derive(Resource, Debug, Default)]
+pub struct CliArgs {
+    /// `--replay <file>`: watch a recorded match instead of playing.
+    pub replay: Option<PathBuf>,
+    /// `--rules <preset or file>`: match rules other than classic.
+    pub rules: Option<String>,
+    /// `--arena <preset or file>`: court other than the standard one.
+    pub arena: Option<String>,
+    /// `--seed <number>`: repeat a run instead of seeding from the clock.
+    pub seed: Option<u64>,
+    /// `--difficulty <easy|normal|hard|impossible>`: skill of the computer.
+    pub difficulty: Difficulty,
+    /// `--tick-rate <hz>`: simulation ticks per second.
+    pub tick_rate: Option<f32>,
+}
+
+impl CliArgs {
+    pub fn parse() -> Self {
+        let mut args = CliArgs::default();
+        let mut iter = std::env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--replay" => args.replay = iter.next().map(PathBuf::from),
+                "--rules" => args.rules = iter.next(),
+                "--arena" => args.arena = iter.next(),
+                "--seed" => {
+                    let seed = iter.next().unwrap_or_default();
+                    match seed.parse() {
+                        Ok(seed) => args.seed = Some(seed),
+                        Err(_) => eprintln!("invalid seed `{seed}`"),
+                    }
+                }
+                "--difficulty" => {
+                    let name = iter.next().unwrap_or_default();
+                    match Difficulty::from_name(&name) {
+                        Some(difficulty) => args.difficulty = difficulty,
+                        None => eprintln!("unknown difficulty `{name}`"),
+                    }
+                }
+                "--tick-rate" => {
+                    let rate = iter.next().unwrap_or_default();
+                    match rate.parse::<f32>() {
+                        Ok(rate) if rate.is_finite() && rate > 0.0 => args.tick_rate = Some(rate),
+                        _ => eprintln!("invalid tick rate `{rate}`"),
+                    }
+                }
+                other => eprintln!("ignoring unknown argument `{other}`"),
+            }
+        }
+
+        args
+    }
+
+    /// The rules asked for with `--rules`, or the default ones.
+    pub fn match_rules(&self) -> MatchRules {
+        let Some(arg) = &self.rules else {
+            return MatchRules::default();
+        };
+
+        MatchRules::from_arg(arg).unwrap_or_else(|err| {
+            eprintln!("failed to load rules `{arg}`: {err}");
+            MatchRules::default()
+        })
+    }
+
+    /// Randomness seeded with `--seed`, or from the clock.
+    pub fn game_rng(&self) -> GameRng {
+        self.seed.map_or_else(GameRng::default, GameRng::new)
+    }
+
+    /// The court asked for with `--arena`, or the standard one.
+    pub fn arena(&self) -> Arena {
+        let Some(arg) = &self.arena else {
+            return Arena::default();
+        };
+
+        Arena::from_arg(arg).unwrap_or_else(|err| {
+            eprintln!("failed to load arena `{arg}`: {err}");
+            Arena::default()
+        })
+    }
+
+    /// The game running at the rate asked for with `--tick-rate`, or the
+    /// default one.
+    pub fn game_plugin(&self) -> GamePlugin {
+        GamePlugin {
+            tick_rate: self.tick_rate.unwrap_or(DEFAULT_TICK_RATE),
+        }
+    }
+}
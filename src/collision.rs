@@ -0,0 +1,170 @@
+//! Continuous collision tests between axis-aligned boxes.
+//!
+//! A moving box against a static one is reduced to a point against the static
+//! box grown by the moving box's half size, so `half` below is always the sum
+//! of both half extents.
+
+use bevy::math::Vec2;
+
+/// First contact of a swept point with a box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    /// Fraction of the motion travelled before contact, `0.0..=1.0`.
+    pub time: f32,
+    /// Outward normal of the face that was hit.
+    pub normal: Vec2,
+}
+
+/// Moves a point from `origin` along `motion` and returns where it enters the
+/// box at `center`, if it does so within the motion.
+///
+/// Starting inside or merely sliding along a face is not a hit; use
+/// [`penetration`] to separate overlapping boxes first.
+pub fn sweep(origin: Vec2, motion: Vec2, center: Vec2, half: Vec2) -> Option<Hit> {
+    let min = center - half;
+    let max = center + half;
+
+    let mut entry = f32::NEG_INFINITY;
+    let mut exit = f32::INFINITY;
+    let mut normal = Vec2::ZERO;
+
+    for axis in 0..2 {
+        let (o, m) = (origin[axis], motion[axis]);
+
+        if m == 0.0 {
+            if o <= min[axis] || o >= max[axis] {
+                return None;
+            }
+            continue;
+        }
+
+        let (near, far, face) = if m > 0.0 {
+            ((min[axis] - o) / m, (max[axis] - o) / m, -1.0)
+        } else {
+            ((max[axis] - o) / m, (min[axis] - o) / m, 1.0)
+        };
+
+        if near > entry {
+            entry = near;
+            normal = Vec2::ZERO;
+            normal[axis] = face;
+        }
+        exit = exit.min(far);
+    }
+
+    if entry > exit || !(0.0..=1.0).contains(&entry) {
+        return None;
+    }
+
+    Some(Hit {
+        time: entry,
+        normal,
+    })
+}
+
+/// How far `point` has to move along the shallowest axis to leave the box at
+/// `center`, or `None` if it is not inside.
+pub fn penetration(point: Vec2, center: Vec2, half: Vec2) -> Option<Vec2> {
+    let delta = point - center;
+    let depth = half - delta.abs();
+
+    if depth.x <= 0.0 || depth.y <= 0.0 {
+        return None;
+    }
+
+    if depth.x < depth.y {
+        Some(Vec2::new(depth.x * delta.x.signum(), 0.0))
+    } else {
+        Some(Vec2::new(0.0, depth.y * delta.y.signum()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hits_the_face_it_meets_first_at_the_time_of_impact() {
+        let hit = |origin, motion| sweep(origin, motion, Vec2::ZERO, Vec2::ONE);
+
+        assert_eq!(
+            hit(Vec2::new(-5.0, 0.0), Vec2::new(8.0, 0.0)),
+            Some(Hit {
+                time: 0.5,
+                normal: Vec2::NEG_X,
+            })
+        );
+        assert_eq!(
+            hit(Vec2::new(5.0, 0.5), Vec2::new(-8.0, 0.0)),
+            Some(Hit {
+                time: 0.5,
+                normal: Vec2::X,
+            })
+        );
+        // crosses x = 1 early but only enters once it drops below y = 1
+        assert_eq!(
+            hit(Vec2::new(1.5, 5.0), Vec2::new(-2.0, -8.0)),
+            Some(Hit {
+                time: 0.5,
+                normal: Vec2::Y,
+            })
+        );
+        assert_eq!(
+            hit(Vec2::new(0.0, -3.0), Vec2::new(0.0, 4.0)),
+            Some(Hit {
+                time: 0.5,
+                normal: Vec2::NEG_Y,
+            })
+        );
+    }
+
+    #[test]
+    fn misses_boxes_out_of_reach_or_only_grazed() {
+        let hit = |origin, motion| sweep(origin, motion, Vec2::ZERO, Vec2::ONE);
+
+        // stops short
+        assert_eq!(hit(Vec2::new(-5.0, 0.0), Vec2::new(2.0, 0.0)), None);
+        // heading away
+        assert_eq!(hit(Vec2::new(-5.0, 0.0), Vec2::new(-8.0, 0.0)), None);
+        // passes above
+        assert_eq!(hit(Vec2::new(-5.0, 3.0), Vec2::new(10.0, 0.0)), None);
+        // slides along the top face
+        assert_eq!(hit(Vec2::new(-5.0, 1.0), Vec2::new(10.0, 0.0)), None);
+        // clips the corner's line without entering
+        assert_eq!(hit(Vec2::new(-3.0, 0.0), Vec2::new(4.0, 4.0)), None);
+    }
+
+    #[test]
+    fn starting_inside_is_left_to_penetration() {
+        assert_eq!(
+            sweep(
+                Vec2::new(0.5, 0.2),
+                Vec2::new(4.0, 0.0),
+                Vec2::ZERO,
+                Vec2::ONE
+            ),
+            None
+        );
+
+        // out along the shallowest axis, on the side the point is on
+        assert_eq!(
+            penetration(Vec2::new(0.5, 0.2), Vec2::ZERO, Vec2::ONE),
+            Some(Vec2::new(0.5, 0.0))
+        );
+        assert_eq!(
+            penetration(Vec2::new(-0.2, -0.75), Vec2::ZERO, Vec2::ONE),
+            Some(Vec2::new(0.0, -0.25))
+        );
+        assert_eq!(
+            penetration(Vec2::new(1.0, 0.0), Vec2::ZERO, Vec2::ONE),
+            None
+        );
+    }
+
+    #[test]
+    fn a_point_that_does_not_move_never_hits() {
+        for origin in [Vec2::ZERO, Vec2::new(1.0, 0.0), Vec2::new(3.0, 0.0)] {
+            assert_eq!(sweep(origin, Vec2::ZERO, Vec2::ZERO, Vec2::ONE), None);
+        }
+    }
+}
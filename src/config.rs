@@ -0,0 +1,37 @@
+//! Small RON files under `config/` that hold the player's preferences.
+
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Reads the file at `path`, falling back to (and writing out) the defaults.
+pub fn load_or_default<T>(path: &str) -> T
+where
+    T: Default + Serialize + DeserializeOwned,
+{
+    match fs::read_to_string(path) {
+        Ok(contents) => match ron::from_str(&contents) {
+            Ok(value) => return value,
+            Err(err) => warn!("Ignoring invalid {path}: {err}"),
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => warn!("Failed to read {path}: {err}"),
+    }
+
+    let value = T::default();
+    if let Err(err) = save(path, &value) {
+        warn!("Failed to write {path}: {err}");
+    }
+    value
+}
+
+pub fn save(path: &str, value: &impl Serialize) -> io::Result<()> {
+    let contents = ron::ser::to_string_pretty(value, default())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    if let Some(dir) = Path::new(path).parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)
+}
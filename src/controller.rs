@@ -0,0 +1,112 @@
+//! Whatever moves a paddle: a person on a keyboard or gamepad, the computer,
+//! or anything else that can decide on a [`PaddleInput`] once per tick.
+//!
+//! A controller is a component implementing [`PaddleController`] that sits on
+//! a paddle entity. Registering it with
+//! [`add_paddle_controller`](AppControllerExt::add_paddle_controller) is all
+//! it takes for any paddle carrying it to be driven by it, and
+//! [`SetController`] hands a paddle over from one controller to another.
+
+use bevy::{
+    ecs::{system::EntityCommand, world::EntityMut},
+    prelude::*,
+};
+
+use crate::{
+    actions::ActionState,
+    game::{GameSet, PaddleInputs, Simulation},
+    sim::{PaddleId, PaddleInput, PongSim},
+    AppState,
+};
+
+/// Decides where a paddle goes next.
+pub trait PaddleController: Component {
+    /// Desired movement of paddle `ctx.paddle` for the coming tick.
+    fn next_input(&mut self, ctx: &ControlContext) -> PaddleInput;
+}
+
+/// What a controller gets to look at each tick.
+pub struct ControlContext<'a> {
+    pub sim: &'a PongSim,
+    /// The paddle being controlled.
+    pub paddle: PaddleId,
+    /// Length of the tick in seconds.
+    pub dt: f32,
+    pub actions: &'a ActionState,
+}
+
+pub trait AppControllerExt {
+    /// Drives every paddle carrying a `T` with it wherever a simulation
+    /// plays: in a match, in the demo behind the main menu and in a replay.
+    fn add_paddle_controller<T: PaddleController>(&mut self) -> &mut Self;
+}
+
+impl AppControllerExt for App {
+    fn add_paddle_controller<T: PaddleController>(&mut self) -> &mut Self {
+        self.init_resource::<ControllerRegistry>();
+        self.world
+            .resource_mut::<ControllerRegistry>()
+            .removers
+            .push(remove_controller::<T>);
+
+        self.add_systems(
+            FixedUpdate,
+            drive_paddles::<T>.in_set(GameSet::Input).run_if(
+                in_state(AppState::Game)
+                    .or_else(in_state(AppState::MainMenu))
+                    .or_else(in_state(AppState::Replay)),
+            ),
+        )
+    }
+}
+
+/// Every registered controller type, so one can be swapped for another
+/// without knowing what was there before.
+#[derive(Resource, Default)]
+struct ControllerRegistry {
+    removers: Vec<fn(&mut EntityMut)>,
+}
+
+fn remove_controller<T: PaddleController>(entity: &mut EntityMut) {
+    entity.remove::<T>();
+}
+
+/// Replaces whatever controls a paddle with the given controller.
+pub struct SetController<T>(pub T);
+
+impl<T: PaddleController> EntityCommand for SetController<T> {
+    fn apply(self, id: Entity, world: &mut World) {
+        let removers = world
+            .get_resource::<ControllerRegistry>()
+            .map(|registry| registry.removers.clone())
+            .unwrap_or_default();
+
+        let Some(mut entity) = world.get_entity_mut(id) else {
+            return;
+        };
+        for remove in removers {
+            remove(&mut entity);
+        }
+        entity.insert(self.0);
+    }
+}
+
+fn drive_paddles<T: PaddleController>(
+    simulation: Res<Simulation>,
+    actions: Res<ActionState>,
+    fixed_time: Res<FixedTime>,
+    mut controllers: Query<(&PaddleId, &mut T)>,
+    mut inputs: ResMut<PaddleInputs>,
+) {
+    let dt = fixed_time.period.as_secs_f32();
+
+    for (&paddle, mut controller) in &mut controllers {
+        let ctx = ControlContext {
+            sim: &simulation.sim,
+            paddle,
+            dt,
+            actions: &actions,
+        };
+        inputs.set(paddle, controller.next_input(&ctx));
+    }
+}
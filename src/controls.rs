@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState, Binding, InputBindings},
+    AppState,
+};
+
+/// Rows of the rebinding screen: player seat (or shared), action and label.
+const ROWS: [(Option<usize>, Action, &str); 18] = [
+    (Some(0), Action::MoveUp, "P1 Up"),
+    (Some(0), Action::MoveDown, "P1 Down"),
+    (Some(0), Action::Serve, "P1 Serve"),
+    (Some(0), Action::Dash, "P1 Dash"),
+    (Some(1), Action::MoveUp, "P2 Up"),
+    (Some(1), Action::MoveDown, "P2 Down"),
+    (Some(1), Action::Serve, "P2 Serve"),
+    (Some(1), Action::Dash, "P2 Dash"),
+    (None, Action::Pause, "Pause"),
+    (None, Action::Confirm, "Confirm"),
+    (None, Action::Back, "Back"),
+    (None, Action::Controls, "Controls"),
+    (None, Action::ToggleInspector, "Inspector"),
+    (None, Action::WatchReplay, "Replay"),
+    (None, Action::PlaybackPause, "Play/Pause"),
+    (None, Action::PlaybackStep, "Step"),
+    (None, Action::FastForward, "Fast Fwd"),
+    (None, Action::SlowMotion, "Slow Mo"),
+];
+
+/// Holding Escape this long while capturing cancels instead of binding it.
+const CANCEL_HOLD: f32 = 0.6;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ControlsScreen>()
+            .add_systems(OnEnter(AppState::Controls), setup)
+            .add_systems(OnExit(AppState::Controls), (cleanup, save_bindings))
+            .add_systems(
+                Update,
+                (rebind, update_rows)
+                    .chain()
+                    .run_if(in_state(AppState::Controls)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ControlsScreen {
+    selected: usize,
+    /// Waiting for the next key or button press to bind to the selected row.
+    capturing: bool,
+    /// Running while Escape is held during capture: released early it is
+    /// bound, held to the end it cancels.
+    escape: Option<Timer>,
+}
+
+#[derive(Component)]
+struct ControlsRoot;
+
+#[derive(Component)]
+struct ControlsRow(usize);
+
+fn setup(mut commands: Commands, mut screen: ResMut<ControlsScreen>) {
+    *screen = ControlsScreen::default();
+
+    let text_style = TextStyle {
+        font_size: 24.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Name::new("Controls Root"),
+            ControlsRoot,
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(
+                "Controls",
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+
+            for index in 0..ROWS.len() {
+                c.spawn((
+                    ControlsRow(index),
+                    TextBundle::from_section("", text_style.clone()),
+                ));
+            }
+
+            c.spawn(TextBundle::from_section(
+                "Confirm to rebind, Back to return, orange keys also trigger another action",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::GRAY,
+                    ..default()
+                },
+            ));
+        });
+}
+
+fn cleanup(mut commands: Commands, roots: Query<Entity, With<ControlsRoot>>) {
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn save_bindings(bindings: Res<InputBindings>) {
+    match bindings.save() {
+        Ok(()) => info!("Bindings saved to {}", InputBindings::PATH),
+        Err(err) => error!("Failed to save bindings: {err}"),
+    }
+}
+
+fn rebind(
+    time: Res<Time>,
+    actions: Res<ActionState>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut screen: ResMut<ControlsScreen>,
+    mut bindings: ResMut<InputBindings>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if let Some(timer) = &mut screen.escape {
+        if timer.tick(time.delta()).finished() {
+            screen.escape = None;
+            screen.capturing = false;
+        } else if !keyboard_input.pressed(KeyCode::Escape) {
+            screen.escape = None;
+            bind_selected(&mut screen, &mut bindings, Binding::Key(KeyCode::Escape));
+        }
+        return;
+    }
+
+    if screen.capturing {
+        // a tap of escape binds it, holding it cancels
+        if keyboard_input.just_pressed(KeyCode::Escape) {
+            screen.escape = Some(Timer::from_seconds(CANCEL_HOLD, TimerMode::Once));
+            return;
+        }
+
+        let pressed = keyboard_input
+            .get_just_pressed()
+            .next()
+            .map(|&key| Binding::Key(key))
+            .or_else(|| {
+                gamepad_buttons
+                    .get_just_pressed()
+                    .next()
+                    .map(|button| Binding::Button(button.button_type))
+            });
+
+        if let Some(binding) = pressed {
+            bind_selected(&mut screen, &mut bindings, binding);
+        }
+        return;
+    }
+
+    if actions.just_pressed(Action::MoveUp) {
+        screen.selected = (screen.selected + ROWS.len() - 1) % ROWS.len();
+    }
+    if actions.just_pressed(Action::MoveDown) {
+        screen.selected = (screen.selected + 1) % ROWS.len();
+    }
+
+    if actions.just_pressed(Action::Confirm) {
+        screen.capturing = true;
+    } else if actions.just_pressed(Action::Back) {
+        app_state_next_state.set(AppState::MainMenu);
+    }
+}
+
+fn bind_selected(screen: &mut ControlsScreen, bindings: &mut InputBindings, binding: Binding) {
+    let (seat, action, label) = ROWS[screen.selected];
+    bindings.rebind(seat, action, binding);
+    screen.capturing = false;
+
+    let clashes = clash_labels(bindings, seat, action);
+    if !clashes.is_empty() {
+        warn!("{binding} for {label} also triggers {}", clashes.join(", "));
+    }
+}
+
+/// Labels of the rows sharing a binding with `action` of `seat` on a screen
+/// where both are live.
+fn clash_labels(
+    bindings: &InputBindings,
+    seat: Option<usize>,
+    action: Action,
+) -> Vec<&'static str> {
+    let mut labels = Vec::new();
+    for &binding in bindings.get(seat, action) {
+        for clash in bindings.clashes(seat, action, binding) {
+            let label = ROWS
+                .iter()
+                .find(|&&(seat, action, _)| (seat, action) == clash)
+                .map(|&(_, _, label)| label);
+            if let Some(label) = label.filter(|label| !labels.contains(label)) {
+                labels.push(label);
+            }
+        }
+    }
+    labels
+}
+
+fn update_rows(
+    screen: Res<ControlsScreen>,
+    bindings: Res<InputBindings>,
+    mut rows: Query<(&ControlsRow, &mut Text)>,
+) {
+    for (row, mut text) in &mut rows {
+        let (seat, action, label) = ROWS[row.0];
+        let selected = row.0 == screen.selected;
+
+        let clashes = clash_labels(&bindings, seat, action);
+
+        let value = if selected && screen.escape.is_some() {
+            "release to bind Escape, keep holding to cancel".to_string()
+        } else if selected && screen.capturing {
+            "press a key or button, hold Escape to cancel".to_string()
+        } else {
+            let value = bindings
+                .get(seat, action)
+                .iter()
+                .map(Binding::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if clashes.is_empty() {
+                value
+            } else {
+                format!("{value} (also {})", clashes.join(", "))
+            }
+        };
+
+        let section = &mut text.sections[0];
+        section.value = format!("{label:<10} {value}");
+        section.style.color = if selected {
+            Color::YELLOW
+        } else if !clashes.is_empty() {
+            Color::ORANGE
+        } else {
+            Color::WHITE
+        };
+    }
+}
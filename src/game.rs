@@ -1,125 +1,634 @@
-use bevy::prelude::*;
+use bevy::{
+    ecs::{component::TableStorage, system::SystemParam},
+    prelude::*,
+    render::camera::ScalingMode,
+};
 use bevy_inspector_egui::{prelude::ReflectInspectorOptions, InspectorOptions};
 use bevy_magic_light_2d::prelude::*;
 
 use crate::{
-    ai::{AiPlugin, AI},
-    ball::{Ball, BallPlugin},
-    player::{Player, PlayerPlugin},
-    ui::GameUiPlugin,
+    ai::{AiPlugin, Difficulty, AI},
+    arena::Arena,
+    ball::{ball_bundle, Ball, BallPlugin},
+    controller::SetController,
+    gamepad::GamepadPlugin,
+    particle::{hit_effect_bundle, HitEffect},
+    pickup::PickupPlugin,
+    player::{Keys, Player, PlayerPlugin},
+    powerup::PowerUps,
+    replay::ReplayPlugin,
+    rng::{GameRng, Rng},
+    rules::{MatchRules, PaddleMotion},
+    settings::Settings,
+    sim::{
+        BallId, PaddleId, PaddleInput, PickupId, PongSim, Side, SimConfig, SimEvent, MAX_PADDLES,
+    },
+    ui::{spawn_hud, GameUiPlugin},
     AppState,
 };
 
-#[derive(Event)]
-pub struct GameOver;
+/// Ticks per second the simulation runs at, independent of the frame rate.
+pub const DEFAULT_TICK_RATE: f32 = 120.0;
+
+/// Sent when a match is decided.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GameOver {
+    pub winner: Side,
+    /// Sets won, or points when playing a single set.
+    pub final_score: [u32; 2],
+}
+
+/// Sets up a brand new match, with fresh paddles, ball and scores. Whoever
+/// sends it also moves to [`AppState::Game`], where the match is built.
+#[derive(Event, Clone, Debug)]
+pub struct StartMatch {
+    pub lineup: Lineup,
+    pub rules: MatchRules,
+}
+
+/// Marks an entity that only exists while the app is in one of the given
+/// states; it is despawned as soon as the app moves to any other.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateScoped(pub &'static [AppState]);
+
+impl StateScoped {
+    /// A match being played, paused or looked back on from the results.
+    pub const MATCH: StateScoped =
+        StateScoped(&[AppState::Game, AppState::Paused, AppState::GameOver]);
+}
+
+/// Wraps a [`SimEvent`] so the rest of the app can react to the simulation.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SimulationEvent(pub SimEvent);
+
+/// The authoritative game state; entities only mirror it.
+#[derive(Resource)]
+pub struct Simulation {
+    pub sim: PongSim,
+    /// State before the latest tick, used to interpolate between ticks.
+    previous: PongSim,
+}
+
+impl Simulation {
+    pub fn new(sim: PongSim) -> Self {
+        Self {
+            previous: sim.clone(),
+            sim,
+        }
+    }
+
+    /// Advances one tick, remembering the current state for interpolation.
+    pub fn step(&mut self, inputs: &[PaddleInput], dt: f32) -> Vec<SimEvent> {
+        self.previous = self.sim.clone();
+        let events = self.sim.step(inputs, dt);
+
+        // don't smear teleports (serves after a goal) across a frame
+        if events.iter().any(|e| matches!(e, SimEvent::Goal { .. })) {
+            self.previous = self.sim.clone();
+        }
+
+        events
+    }
+
+    /// Starts a new match on `config` under `rules`, drawing from `rng` and
+    /// keeping the paddle speeds.
+    pub fn restart(&mut self, config: SimConfig, rules: MatchRules, rng: Rng) {
+        let mut sim = PongSim::new(config, rules).with_rng(rng);
+        for (paddle, previous) in sim.paddles.iter_mut().zip(&self.sim.paddles) {
+            paddle.speed = previous.speed;
+        }
+        *self = Self::new(sim);
+    }
+
+    /// Stops interpolating towards the latest tick, so a frozen match holds
+    /// still instead of jittering.
+    pub fn settle(&mut self) {
+        self.previous = self.sim.clone();
+    }
+
+    /// Where ball `id` is drawn, or `None` once it has left play. A ball
+    /// split off during the latest tick starts out where it split.
+    pub fn ball_position(&self, id: BallId, alpha: f32) -> Option<Vec2> {
+        let current = self.sim.ball(id)?.position;
+        let previous = self.previous.ball(id).map_or(current, |ball| ball.position);
+        Some(previous.lerp(current, alpha))
+    }
+
+    /// Where paddle `id` is drawn, or `None` if it isn't in play, as on the
+    /// frame a court with a different formation is swapped in.
+    pub fn paddle_position(&self, id: PaddleId, alpha: f32) -> Option<Vec2> {
+        let previous = self.previous.paddles.get(id.index())?;
+        let current = self.sim.paddles.get(id.index())?;
+        Some(previous.position.lerp(current.position, alpha))
+    }
+}
+
+impl FromWorld for Simulation {
+    fn from_world(world: &mut World) -> Self {
+        let arena = world.get_resource::<Arena>().cloned().unwrap_or_default();
+        let rules = world
+            .get_resource::<MatchRules>()
+            .cloned()
+            .unwrap_or_default();
+        let power_ups = world
+            .get_resource::<PowerUps>()
+            .cloned()
+            .unwrap_or_default();
+        let config = SimConfig::new(&arena).with_power_ups(power_ups);
+        Self::new(PongSim::new(config, rules))
+    }
+}
+
+/// Points and sets won by each side in the current match, mirrored from the
+/// simulation. Scores belong to a [`Side`], not to whoever controls it.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct MatchScore {
+    /// Points in the current set, indexed by [`Side::index`].
+    pub points: [u32; 2],
+    /// Sets won this match, indexed by [`Side::index`].
+    pub sets: [u32; 2],
+}
+
+impl MatchScore {
+    pub fn points(&self, side: Side) -> u32 {
+        self.points[side.index()]
+    }
+
+    pub fn sets(&self, side: Side) -> u32 {
+        self.sets[side.index()]
+    }
+
+    /// Points `side` is ahead by in the current set, negative when behind.
+    pub fn lead(&self, side: Side) -> i32 {
+        self.points(side) as i32 - self.points(side.opponent()) as i32
+    }
+}
+
+/// Paddles carry the [`Side`] they defend, so systems can tell them apart
+/// without caring whether a player or the AI is in control.
+impl Component for Side {
+    type Storage = TableStorage;
+}
+
+/// Paddles also carry their [`PaddleId`], telling teammates apart.
+impl Component for PaddleId {
+    type Storage = TableStorage;
+}
+
+/// Balls carry the [`BallId`] of the simulated ball they show.
+impl Component for BallId {
+    type Storage = TableStorage;
+}
+
+/// Power-ups on court carry the [`PickupId`] of the one they show.
+impl Component for PickupId {
+    type Storage = TableStorage;
+}
+
+/// How far the current frame is between the last tick and the next one.
+pub fn interpolation_alpha(fixed_time: &FixedTime) -> f32 {
+    (fixed_time.accumulated().as_secs_f32() / fixed_time.period.as_secs_f32()).clamp(0.0, 1.0)
+}
+
+/// Inputs for the next simulation step, indexed by [`PaddleId::index`].
+#[derive(Resource, Default)]
+pub struct PaddleInputs(pub [PaddleInput; MAX_PADDLES]);
+
+impl PaddleInputs {
+    pub fn set(&mut self, id: PaddleId, input: PaddleInput) {
+        self.0[id.index()] = input;
+    }
+}
+
+/// Who drives one paddle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Seat {
+    #[default]
+    Computer,
+    /// A person steering with the given keys.
+    Human(Keys),
+}
+
+impl Seat {
+    pub const ALL: [Seat; 4] = [
+        Seat::Computer,
+        Seat::Human(Keys::First),
+        Seat::Human(Keys::Second),
+        Seat::Human(Keys::Any),
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Seat::Computer => "Computer",
+            Seat::Human(Keys::First) => "Player 1",
+            Seat::Human(Keys::Second) => "Player 2",
+            Seat::Human(Keys::Any) => "Either Player",
+        }
+    }
+
+    /// The seat after this one, wrapping around.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&seat| seat == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+/// Who drives which paddle, by [`PaddleId::index`], picked before a match
+/// starts. Any mix works, from two people a side to the computer on every
+/// paddle; seats of teammates sit out in singles.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lineup(pub [Seat; MAX_PADDLES]);
+
+/// One person on the left against the computer.
+impl Default for Lineup {
+    fn default() -> Self {
+        let mut seats = [Seat::Computer; MAX_PADDLES];
+        seats[PaddleId::from(Side::Left).index()] = Seat::Human(Keys::Any);
+        Self(seats)
+    }
+}
 
-pub struct GamePlugin;
+impl Lineup {
+    pub fn seat(&self, id: PaddleId) -> Seat {
+        self.0[id.index()]
+    }
+
+    pub fn seat_mut(&mut self, id: PaddleId) -> &mut Seat {
+        &mut self.0[id.index()]
+    }
+}
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameSet {
+    /// Controllers write [`PaddleInputs`], once per tick in `FixedUpdate`.
+    Input,
+    /// The simulation advances, once per tick in `FixedUpdate`.
+    Simulate,
+    /// Entities are updated from the simulation every frame in `Update`.
+    Sync,
+}
+
+pub struct GamePlugin {
+    /// Simulation ticks per second.
+    pub tick_rate: f32,
+}
+
+impl Default for GamePlugin {
+    fn default() -> Self {
+        Self {
+            tick_rate: DEFAULT_TICK_RATE,
+        }
+    }
+}
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<GameOver>()
+            .add_event::<SimulationEvent>()
+            .add_event::<StartMatch>()
+            .insert_resource(FixedTime::new_from_secs(1.0 / self.tick_rate))
+            .init_resource::<GameRng>()
+            .init_resource::<Arena>()
+            .init_resource::<MatchRules>()
+            .init_resource::<Simulation>()
+            .init_resource::<PaddleInputs>()
+            .init_resource::<Lineup>()
+            .init_resource::<MatchScore>()
+            .configure_sets(FixedUpdate, (GameSet::Input, GameSet::Simulate).chain())
             .add_plugins(GameUiPlugin)
             .add_plugins(BallPlugin)
+            .add_plugins(PickupPlugin)
             .add_plugins(PlayerPlugin)
+            .add_plugins(GamepadPlugin)
             .add_plugins(AiPlugin)
+            .add_plugins(ReplayPlugin)
+            .register_type::<GameRng>()
             .register_type::<Paddle>()
-            .register_type::<Score>()
-            .add_systems(Startup, setup)
+            .register_type::<MatchScore>()
+            .add_systems(OnEnter(AppState::Game), start_match)
+            .add_systems(
+                FixedUpdate,
+                (
+                    apply_paddle_tuning.in_set(GameSet::Input),
+                    step_simulation.in_set(GameSet::Simulate),
+                )
+                    .run_if(in_state(AppState::Game)),
+            )
             .add_systems(
                 Update,
-                (check_goal, check_win).run_if(in_state(AppState::Game)),
+                (
+                    fit_to_arena.run_if(resource_changed::<Arena>()),
+                    sync_paddles,
+                    sync_scores,
+                    log_results.run_if(not(in_state(AppState::MainMenu))),
+                    end_match.run_if(in_state(AppState::Game)),
+                )
+                    .in_set(GameSet::Sync),
             );
+
+        for state in AppState::variants() {
+            app.add_systems(OnExit(state), despawn_out_of_scope);
+        }
+    }
+}
+
+/// Runs on leaving any state, by which time [`State`] holds the next one.
+fn despawn_out_of_scope(
+    mut commands: Commands,
+    state: Res<State<AppState>>,
+    scoped: Query<(Entity, &StateScoped)>,
+) {
+    for (entity, scope) in &scoped {
+        if !scope.0.contains(state.get()) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Spawns everything drawn on the court: the paddles, the ball, the center
+/// line, the score display and the hit effect.
+#[derive(SystemParam)]
+pub struct CourtSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    arena: Res<'w, Arena>,
+    hit_effect: Res<'w, HitEffect>,
+    scoped: Query<'w, 's, (Entity, &'static StateScoped)>,
+}
+
+impl<'w, 's> CourtSpawner<'w, 's> {
+    pub fn arena(&self) -> &Arena {
+        &self.arena
+    }
+
+    pub fn commands(&mut self) -> &mut Commands<'w, 's> {
+        &mut self.commands
+    }
+
+    /// A fresh court for `sim` that lives as long as `scope`, replacing
+    /// anything else spawned for that scope. Returns the paddle entities,
+    /// indexed by [`PaddleId::index`].
+    pub fn spawn(&mut self, scope: StateScoped, sim: &PongSim) -> Vec<Entity> {
+        let commands = &mut self.commands;
+        let arena = &self.arena;
+
+        for (entity, existing) in &self.scoped {
+            if *existing == scope {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+
+        // create the center line, 2px width, dashed line
+        commands.spawn((
+            Name::new("Center Line"),
+            CenterLine,
+            SpriteBundle {
+                transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
+                sprite: Sprite {
+                    color: Color::WHITE,
+                    custom_size: Some(Vec2::new(1.0, arena.size.y)),
+                    anchor: default(),
+                    ..default()
+                },
+                ..Default::default()
+            },
+            SkylightLight2D {
+                intensity: 0.001,
+                color: Color::WHITE,
+            },
+            scope,
+        ));
+
+        for ball in &sim.balls {
+            commands.spawn((ball_bundle(arena, ball, Color::WHITE), scope));
+        }
+        commands.spawn((hit_effect_bundle(&self.hit_effect), scope));
+        spawn_hud(commands, scope);
+
+        sim.paddle_ids()
+            .map(|id| {
+                let paddle = Paddle {
+                    speed: sim.paddle(id).speed,
+                    motion: sim.paddle(id).motion,
+                };
+                commands
+                    .spawn((paddle_bundle(arena, id, paddle), scope))
+                    .id()
+            })
+            .collect()
     }
 }
 
-fn setup(mut commands: Commands) {
-    // create the center line, 2px width, dashed line
-    commands.spawn((
-        Name::new("Center Line"),
+fn paddle_bundle(arena: &Arena, id: PaddleId, paddle: Paddle) -> impl Bundle {
+    let (name, light) = match id.side {
+        Side::Left => ("Left Paddle", Color::rgb_u8(28, 28, 255)),
+        Side::Right => ("Right Paddle", Color::rgb_u8(255, 28, 28)),
+    };
+    let name = match id.slot {
+        0 => name.to_string(),
+        slot => format!("{name} {}", slot + 1),
+    };
+
+    (
+        Name::new(name),
         SpriteBundle {
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
+            transform: Transform::from_translation(arena.paddle_start(id.side).extend(0.0)),
             sprite: Sprite {
                 color: Color::WHITE,
-                custom_size: Some(Vec2::new(1.0, 144.0)),
+                custom_size: Some(arena.paddle_size),
                 anchor: default(),
                 ..default()
             },
             ..Default::default()
         },
-        SkylightLight2D {
-            intensity: 0.001,
-            color: Color::WHITE,
+        OmniLightSource2D {
+            intensity: 0.2,
+            color: light,
+            falloff: Vec3::new(0.15, 0.25, 0.005),
+            ..default()
+        },
+        LightOccluder2D {
+            h_size: arena.paddle_size,
         },
-    ));
+        id.side,
+        id,
+        paddle,
+    )
 }
 
-fn check_goal(
-    mut ball_query: Query<(&mut Ball, &mut Transform)>,
-    mut player_score: Query<&mut Score, With<Player>>,
-    mut ai_score: Query<&mut Score, (With<AI>, Without<Player>)>,
-    mut paddles: Query<&mut Transform, (With<Paddle>, Without<Ball>)>,
+/// Resizes everything drawn on the court and frames it with the camera.
+/// Positions follow on their own once the simulation runs on the new court.
+fn fit_to_arena(
+    arena: Res<Arena>,
+    mut paddles: Query<(&mut Sprite, &mut LightOccluder2D), With<Paddle>>,
+    mut balls: Query<(&mut Sprite, &mut LightOccluder2D), (With<Ball>, Without<Paddle>)>,
+    mut center_lines: Query<&mut Sprite, (With<CenterLine>, Without<Paddle>, Without<Ball>)>,
+    mut cameras: Query<&mut OrthographicProjection, With<FloorCamera>>,
 ) {
-    let mut ball = ball_query.single_mut();
+    for (mut sprite, mut occluder) in &mut paddles {
+        sprite.custom_size = Some(arena.paddle_size);
+        occluder.h_size = arena.paddle_size;
+    }
+    for (mut sprite, mut occluder) in &mut balls {
+        sprite.custom_size = Some(arena.ball_size);
+        occluder.h_size = arena.ball_size;
+    }
+    for mut sprite in &mut center_lines {
+        sprite.custom_size = Some(Vec2::new(1.0, arena.size.y));
+    }
+    for mut projection in &mut cameras {
+        projection.scaling_mode = arena_scaling(&arena);
+    }
+}
 
-    let ball_size = Vec2::new(4.0, 4.0);
+/// Camera scaling that just fits the court between the goal lines and walls.
+pub fn arena_scaling(arena: &Arena) -> ScalingMode {
+    ScalingMode::AutoMin {
+        min_width: arena.size.x,
+        min_height: arena.size.y,
+    }
+}
 
-    let ball_left = ball.1.translation.x - ball_size.x / 2.0;
-    let ball_right = ball.1.translation.x + ball_size.x / 2.0;
+/// Builds the match asked for by the latest [`StartMatch`] on a court of its
+/// own. Resuming from the pause menu enters the game without one and carries
+/// on with the match as it was.
+pub(crate) fn start_match(
+    mut start_events: EventReader<StartMatch>,
+    mut court: CourtSpawner,
+    mut lineup: ResMut<Lineup>,
+    difficulty: Res<Difficulty>,
+    settings: Res<Settings>,
+    power_ups: Res<PowerUps>,
+    mut game_rng: ResMut<GameRng>,
+    mut simulation: ResMut<Simulation>,
+) {
+    let Some(start) = start_events.iter().last().cloned() else {
+        return;
+    };
+    *lineup = start.lineup;
 
-    if ball_left < -128.0 {
-        info!("Player 2 scores!");
-        // reset ball
-        ball.0.velocity = Vec2::new(-50.0, 0.0);
-        ball.1.translation = Vec3::new(0.0, 0.0, 0.0);
-        ai_score.single_mut().value += 1;
+    let rng = game_rng.fork();
+    info!(
+        "Starting a {} match on the {} court, seed {}",
+        start.rules.name,
+        court.arena().name,
+        rng.seed()
+    );
 
-        // reset all paddles y to 0
-        for mut paddle_transform in &mut paddles {
-            paddle_transform.translation.y = 0.0;
-        }
+    let config = SimConfig::new(court.arena()).with_power_ups(power_ups.clone());
+    let sim = PongSim::new(config, start.rules).with_rng(rng);
+    let paddles = court.spawn(StateScoped::MATCH, &sim);
+
+    for (id, paddle) in sim.paddle_ids().zip(paddles) {
+        let mut entity = court.commands().entity(paddle);
+        match start.lineup.seat(id) {
+            Seat::Human(keys) => entity.add(SetController(Player { keys, ..default() })),
+            Seat::Computer => entity.add(SetController(
+                AI::new(*difficulty, game_rng.fork()).adaptive(settings.adaptive_ai),
+            )),
+        };
     }
-    if ball_right > 128.0 {
-        info!("Player 1 scores!");
-        // reset ball
-        ball.0.velocity = Vec2::new(50.0, 0.0);
-        ball.1.translation = Vec3::new(0.0, 0.0, 0.0);
-        player_score.single_mut().value += 1;
 
-        // reset all paddles y to 0
-        for mut paddle_transform in &mut paddles {
-            paddle_transform.translation.y = 0.0;
+    *simulation = Simulation::new(sim);
+}
+
+/// Keeps paddle speed and motion tweakable from the inspector during a live
+/// match. The replay being recorded picks up the changes, so they play back
+/// on the same tick.
+fn apply_paddle_tuning(mut simulation: ResMut<Simulation>, paddles: Query<(&PaddleId, &Paddle)>) {
+    for (id, paddle) in &paddles {
+        if let Some(state) = simulation.sim.paddles.get_mut(id.index()) {
+            state.speed = paddle.speed;
+            state.motion = paddle.motion;
         }
     }
 }
 
-/// If a player has 11 points, they win!
-fn check_win(
-    mut player_score: Query<&mut Score, With<Player>>,
-    mut ai_score: Query<&mut Score, (With<AI>, Without<Player>)>,
+pub(crate) fn step_simulation(
+    mut simulation: ResMut<Simulation>,
+    inputs: Res<PaddleInputs>,
+    mut sim_events: EventWriter<SimulationEvent>,
+    fixed_time: Res<FixedTime>,
 ) {
-    let mut player_score = player_score.single_mut();
-    let mut ai_score = ai_score.single_mut();
+    let events = simulation.step(&inputs.0, fixed_time.period.as_secs_f32());
+    sim_events.send_batch(events.into_iter().map(SimulationEvent));
+}
 
-    if player_score.value == 11 {
-        info!("Player 1 wins!");
-        player_score.value = 0;
-        ai_score.value = 0;
+fn sync_paddles(
+    simulation: Res<Simulation>,
+    fixed_time: Res<FixedTime>,
+    mut paddles: Query<(&PaddleId, &mut Transform, &mut Sprite, &mut LightOccluder2D)>,
+) {
+    let alpha = interpolation_alpha(&fixed_time);
+
+    for (id, mut transform, mut sprite, mut occluder) in &mut paddles {
+        let (Some(position), Some(state)) = (
+            simulation.paddle_position(*id, alpha),
+            simulation.sim.paddles.get(id.index()),
+        ) else {
+            continue;
+        };
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+
+        // grown or shrunk by power-ups
+        if sprite.custom_size != Some(state.size) {
+            sprite.custom_size = Some(state.size);
+            occluder.h_size = state.size;
+        }
     }
-    if ai_score.value == 11 {
-        info!("Player 2 wins!");
-        player_score.value = 0;
-        ai_score.value = 0;
+}
+
+fn sync_scores(simulation: Res<Simulation>, mut score: ResMut<MatchScore>) {
+    score.set_if_neq(MatchScore {
+        points: Side::ALL.map(|side| simulation.sim.score(side)),
+        sets: Side::ALL.map(|side| simulation.sim.sets_won(side)),
+    });
+}
+
+fn log_results(mut sim_events: EventReader<SimulationEvent>) {
+    for SimulationEvent(event) in sim_events.iter() {
+        match event {
+            SimEvent::Goal { scorer, .. } => info!("{} scores!", player_name(*scorer)),
+            SimEvent::SetWon { winner } => info!("{} takes the set!", player_name(*winner)),
+            SimEvent::MatchWon { winner } => info!("{} wins!", player_name(*winner)),
+            _ => {}
+        }
     }
 }
 
-#[derive(Component, InspectorOptions, Default, Reflect)]
-#[reflect(Component, InspectorOptions)]
-pub struct Paddle {
-    pub speed: f32,
+fn end_match(
+    mut sim_events: EventReader<SimulationEvent>,
+    simulation: Res<Simulation>,
+    mut game_over_events: EventWriter<GameOver>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    for SimulationEvent(event) in sim_events.iter() {
+        if let SimEvent::MatchWon { winner } = *event {
+            game_over_events.send(GameOver {
+                winner,
+                final_score: simulation.sim.match_score(),
+            });
+            app_state_next_state.set(AppState::GameOver);
+        }
+    }
+}
+
+fn player_name(side: Side) -> &'static str {
+    match side {
+        Side::Left => "Player 1",
+        Side::Right => "Player 2",
+    }
 }
 
+#[derive(Component)]
+struct CenterLine;
+
 #[derive(Component, InspectorOptions, Default, Reflect)]
 #[reflect(Component, InspectorOptions)]
-pub struct Score {
-    pub value: u32,
+pub struct Paddle {
+    /// Top speed, in units per second.
+    pub speed: f32,
+    /// Taken from [`MatchRules::paddle_motion`] whenever a match starts.
+    pub motion: PaddleMotion,
 }
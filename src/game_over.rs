@@ -0,0 +1,198 @@
+use bevy::{app::AppExit, ecs::query::Has, prelude::*};
+
+use crate::{
+    actions::{Action, ActionState},
+    ai::AI,
+    game::{GameOver, Lineup, Paddle, Simulation, StartMatch},
+    sim::Side,
+    ui::team_label,
+    AppState,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ResultsOption {
+    Rematch,
+    MainMenu,
+    Quit,
+}
+
+const OPTIONS: [(ResultsOption, &str); 3] = [
+    (ResultsOption::Rematch, "Rematch"),
+    (ResultsOption::MainMenu, "Main Menu"),
+    (ResultsOption::Quit, "Quit"),
+];
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ResultsScreen>()
+            .add_systems(OnEnter(AppState::GameOver), setup)
+            .add_systems(OnExit(AppState::GameOver), cleanup)
+            .add_systems(
+                Update,
+                (choose_option, update_options)
+                    .chain()
+                    .run_if(in_state(AppState::GameOver)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct ResultsScreen {
+    selected: usize,
+}
+
+#[derive(Component)]
+struct ResultsRoot;
+
+#[derive(Component)]
+struct ResultsOptionLabel(usize);
+
+fn setup(
+    mut commands: Commands,
+    mut screen: ResMut<ResultsScreen>,
+    mut game_over_events: EventReader<GameOver>,
+    simulation: Res<Simulation>,
+    fixed_time: Res<FixedTime>,
+    paddles: Query<(&Side, Has<AI>), With<Paddle>>,
+) {
+    *screen = ResultsScreen::default();
+
+    let Some(game_over) = game_over_events.iter().last().copied() else {
+        return;
+    };
+
+    let winner = if paddles.iter().any(|(side, _)| *side == game_over.winner) {
+        team_label(game_over.winner, paddles.iter())
+    } else {
+        "Nobody"
+    };
+
+    let [left, right] = game_over.final_score;
+    let score = if simulation.sim.rules.sets > 1 {
+        format!("Sets {left} - {right}")
+    } else {
+        format!("{left} - {right}")
+    };
+
+    let stats = &simulation.sim.stats;
+    let seconds = (simulation.sim.tick as f32 * fixed_time.period.as_secs_f32()) as u32;
+    let lines = [
+        format!("Match time    {}:{:02}", seconds / 60, seconds % 60),
+        format!("Paddle hits   {}", stats.paddle_hits),
+        format!("Longest rally {}", stats.longest_rally),
+        format!("Top speed     {:.0}", stats.top_speed),
+    ];
+
+    let text_style = TextStyle {
+        font_size: 24.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Name::new("Results Root"),
+            ResultsRoot,
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn(TextBundle::from_section(
+                format!("{winner} wins!"),
+                TextStyle {
+                    font_size: 48.0,
+                    ..default()
+                },
+            ));
+            c.spawn(TextBundle::from_section(
+                score,
+                TextStyle {
+                    font_size: 32.0,
+                    ..default()
+                },
+            ));
+
+            for line in lines {
+                c.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::GRAY,
+                        ..default()
+                    },
+                ));
+            }
+
+            for (index, (_, label)) in OPTIONS.iter().enumerate() {
+                c.spawn((
+                    ResultsOptionLabel(index),
+                    TextBundle::from_section(*label, text_style.clone()),
+                ));
+            }
+        });
+}
+
+fn cleanup(mut commands: Commands, roots: Query<Entity, With<ResultsRoot>>) {
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn choose_option(
+    actions: Res<ActionState>,
+    mut screen: ResMut<ResultsScreen>,
+    lineup: Res<Lineup>,
+    simulation: Res<Simulation>,
+    mut start_events: EventWriter<StartMatch>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if actions.just_pressed(Action::MoveUp) {
+        screen.selected = (screen.selected + OPTIONS.len() - 1) % OPTIONS.len();
+    }
+    if actions.just_pressed(Action::MoveDown) {
+        screen.selected = (screen.selected + 1) % OPTIONS.len();
+    }
+
+    if actions.just_pressed(Action::Back) {
+        app_state_next_state.set(AppState::MainMenu);
+        return;
+    }
+    if !actions.just_pressed(Action::Confirm) {
+        return;
+    }
+
+    match OPTIONS[screen.selected].0 {
+        ResultsOption::Rematch => {
+            start_events.send(StartMatch {
+                lineup: *lineup,
+                rules: simulation.sim.rules.clone(),
+            });
+            app_state_next_state.set(AppState::Game);
+        }
+        ResultsOption::MainMenu => app_state_next_state.set(AppState::MainMenu),
+        ResultsOption::Quit => app_exit_events.send(AppExit),
+    }
+}
+
+fn update_options(screen: Res<ResultsScreen>, mut labels: Query<(&ResultsOptionLabel, &mut Text)>) {
+    for (label, mut text) in &mut labels {
+        text.sections[0].style.color = if label.0 == screen.selected {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+    }
+}
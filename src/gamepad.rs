@@ -0,0 +1,78 @@
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+};
+
+use crate::{player::Player, sim::PaddleId};
+
+/// Stick deflection below this is ignored, so worn sticks don't drift.
+const DEAD_ZONE: f32 = 0.2;
+
+pub struct GamepadPlugin;
+
+impl Plugin for GamepadPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (log_connections, assign_gamepads).chain());
+    }
+}
+
+fn log_connections(mut connection_events: EventReader<GamepadConnectionEvent>) {
+    for event in connection_events.iter() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!("Gamepad {} connected: {}", event.gamepad.id, info.name)
+            }
+            GamepadConnection::Disconnected => {
+                info!("Gamepad {} disconnected", event.gamepad.id)
+            }
+        }
+    }
+}
+
+/// Gives every human player without a controller the next free gamepad, in
+/// paddle order, and takes away gamepads that were unplugged.
+fn assign_gamepads(gamepads: Res<Gamepads>, mut players: Query<(&PaddleId, &mut Player)>) {
+    let mut players: Vec<_> = players.iter_mut().collect();
+    players.sort_by_key(|(paddle, _)| paddle.index());
+
+    for (_, player) in &mut players {
+        if player
+            .gamepad
+            .is_some_and(|gamepad| !gamepads.contains(gamepad))
+        {
+            player.gamepad = None;
+        }
+    }
+
+    let mut taken: Vec<_> = players
+        .iter()
+        .filter_map(|(_, player)| player.gamepad)
+        .collect();
+
+    for (paddle, player) in &mut players {
+        if player.gamepad.is_some() {
+            continue;
+        }
+
+        let Some(free) = gamepads.iter().find(|gamepad| !taken.contains(gamepad)) else {
+            return;
+        };
+
+        info!("Gamepad {} controls {:?}", free.id, paddle);
+        player.gamepad = Some(free);
+        taken.push(free);
+    }
+}
+
+/// Left stick deflection of `gamepad`, with the dead zone cut out.
+pub fn stick_axis(gamepad: Gamepad, axes: &Axis<GamepadAxis>) -> f32 {
+    let stick = axes
+        .get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+        .unwrap_or(0.0);
+
+    if stick.abs() < DEAD_ZONE {
+        0.0
+    } else {
+        stick.signum() * (stick.abs() - DEAD_ZONE) / (1.0 - DEAD_ZONE)
+    }
+}
@@ -1,20 +1,43 @@
 // #![windows_subsystem = "windows"]
 
+mod actions;
 mod ai;
+mod arena;
 mod ball;
+mod cli;
+mod collision;
+mod config;
+mod controller;
+mod controls;
 mod game;
+mod game_over;
+mod gamepad;
+mod menu;
 mod particle;
+mod pause;
+mod pickup;
 mod player;
+mod powerup;
+mod replay;
+mod rng;
+mod rules;
+mod settings;
+mod sim;
 mod ui;
 
+use actions::{action_toggle_active, Action, ActionState, ActionsPlugin};
+use arena::Arena;
 use bevy_magic_light_2d::prelude::*;
+use cli::CliArgs;
+use controls::ControlsPlugin;
 use game::*;
+use game_over::GameOverPlugin;
+use menu::MenuPlugin;
 
 use bevy::{
-    input::{common_conditions::input_toggle_active, keyboard::KeyboardInput, ButtonState},
     prelude::*,
     render::{
-        camera::{RenderTarget, ScalingMode},
+        camera::RenderTarget,
         settings::{WgpuFeatures, WgpuSettings},
         RenderPlugin,
     },
@@ -22,6 +45,8 @@ use bevy::{
 
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use particle::ParticlePlugin;
+use pause::PausePlugin;
+use settings::SettingsPlugin;
 
 fn main() {
     let mut wgpu_settings = WgpuSettings::default();
@@ -29,6 +54,9 @@ fn main() {
         .features
         .set(WgpuFeatures::VERTEX_WRITABLE_STORAGE, true);
 
+    let args = CliArgs::parse();
+    let game = args.game_plugin();
+
     App::new()
         .add_plugins((
             DefaultPlugins
@@ -57,19 +85,34 @@ fn main() {
         .register_type::<BevyMagicLight2DSettings>()
         .register_type::<LightPassParams>()
         .insert_resource(ClearColor(Color::DARK_GRAY))
+        .insert_resource(args.game_rng())
+        .insert_resource(args.arena())
+        .insert_resource(args.match_rules())
+        .insert_resource(args.difficulty)
+        .insert_resource(args)
         .add_state::<AppState>()
+        .add_plugins(SettingsPlugin)
+        .add_plugins(ActionsPlugin)
         .add_plugins(
-            WorldInspectorPlugin::default().run_if(input_toggle_active(false, KeyCode::Grave)),
+            WorldInspectorPlugin::default()
+                .run_if(action_toggle_active(false, Action::ToggleInspector)),
         )
+        .add_plugins(MenuPlugin)
+        .add_plugins(ControlsPlugin)
+        .add_plugins(PausePlugin)
+        .add_plugins(GameOverPlugin)
         .add_plugins(ParticlePlugin)
-        .add_plugins(GamePlugin)
+        .add_plugins(game)
         .add_systems(Startup, camera.after(setup_post_processing_camera))
-        .add_systems(Update, transition_to_game_state)
         .add_systems(Update, transition_to_main_menu_state)
         .run();
 }
 
-pub fn camera(mut commands: Commands, post_processing_target: Res<PostProcessingTarget>) {
+pub fn camera(
+    mut commands: Commands,
+    post_processing_target: Res<PostProcessingTarget>,
+    arena: Res<Arena>,
+) {
     let render_target = post_processing_target
         .handles
         .as_ref()
@@ -88,10 +131,7 @@ pub fn camera(mut commands: Commands, post_processing_target: Res<PostProcessing
                 projection: OrthographicProjection {
                     far: 1000.,
                     near: -1000.,
-                    scaling_mode: ScalingMode::AutoMin {
-                        min_width: 256.0,
-                        min_height: 144.0,
-                    },
+                    scaling_mode: arena_scaling(&arena),
                     ..Default::default()
                 },
                 ..Default::default()
@@ -102,38 +142,17 @@ pub fn camera(mut commands: Commands, post_processing_target: Res<PostProcessing
         .insert(UiCameraConfig { show_ui: true });
 }
 
-pub fn transition_to_game_state(
-    mut key_evr: EventReader<KeyboardInput>,
-    app_state: Res<State<AppState>>,
-    mut app_state_next_state: ResMut<NextState<AppState>>,
-    mut app_exit_events: ResMut<Events<bevy::app::AppExit>>,
-) {
-    if *app_state.get() == AppState::Game {
-        return;
-    }
-
-    for ev in key_evr.iter() {
-        if matches!(ev.state, ButtonState::Pressed) {
-            if ev.key_code == Some(KeyCode::Escape) {
-                app_exit_events.send(bevy::app::AppExit);
-                return;
-            }
-
-            app_state_next_state.set(AppState::Game);
-        }
-    }
-}
-
 pub fn transition_to_main_menu_state(
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<ActionState>,
     app_state: Res<State<AppState>>,
     mut app_state_next_state: ResMut<NextState<AppState>>,
 ) {
-    if *app_state.get() == AppState::MainMenu {
+    // every other screen handles its own way back
+    if *app_state.get() != AppState::Replay {
         return;
     }
 
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(Action::Pause) || actions.just_pressed(Action::Back) {
         app_state_next_state.set(AppState::MainMenu);
     }
 }
@@ -143,5 +162,8 @@ pub enum AppState {
     #[default]
     MainMenu,
     Game,
+    Paused,
     GameOver,
+    Replay,
+    Controls,
 }
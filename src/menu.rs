@@ -0,0 +1,363 @@
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    actions::{Action, ActionState},
+    ai::{Difficulty, AI},
+    arena::Arena,
+    controller::SetController,
+    game::{
+        step_simulation, CourtSpawner, GameSet, Lineup, PaddleInputs, Simulation, StartMatch,
+        StateScoped,
+    },
+    powerup::PowerUps,
+    replay::watch_latest_replay,
+    rng::GameRng,
+    rules::MatchRules,
+    settings::Settings,
+    sim::{PaddleId, PongSim, Side, SimConfig},
+    AppState,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MenuItem {
+    Play,
+    /// Who drives the paddle.
+    Seat(PaddleId),
+    Difficulty,
+    AdaptiveAi,
+    Rules,
+    Doubles,
+    Multiball,
+    PowerUps,
+    Court,
+    WatchReplay,
+    Controls,
+    Quit,
+}
+
+const ITEMS: [(MenuItem, &str); 15] = [
+    (MenuItem::Play, "Play"),
+    (MenuItem::Seat(PaddleId::new(Side::Left, 0)), "Left"),
+    (MenuItem::Seat(PaddleId::new(Side::Left, 1)), "Left Mate"),
+    (MenuItem::Seat(PaddleId::new(Side::Right, 0)), "Right"),
+    (MenuItem::Seat(PaddleId::new(Side::Right, 1)), "Right Mate"),
+    (MenuItem::Difficulty, "Difficulty"),
+    (MenuItem::AdaptiveAi, "Adaptive AI"),
+    (MenuItem::Rules, "Rules"),
+    (MenuItem::Doubles, "Doubles"),
+    (MenuItem::Multiball, "Multiball"),
+    (MenuItem::PowerUps, "Power-ups"),
+    (MenuItem::Court, "Court"),
+    (MenuItem::WatchReplay, "Watch Last Replay"),
+    (MenuItem::Controls, "Controls"),
+    (MenuItem::Quit, "Quit"),
+];
+
+const BUTTON_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.05);
+const SELECTED_BUTTON_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.15);
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MainMenu>()
+            .add_systems(
+                OnEnter(AppState::MainMenu),
+                (setup, (start_attract_mode, spawn_attract_court).chain()),
+            )
+            .add_systems(OnExit(AppState::MainMenu), cleanup)
+            .add_systems(
+                Update,
+                (
+                    navigate_menu,
+                    update_items,
+                    (start_attract_mode, spawn_attract_court).chain().run_if(
+                        resource_changed::<Arena>().or_else(resource_changed::<MatchRules>()),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::MainMenu)),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    restart_finished_demo.before(GameSet::Input),
+                    step_simulation.in_set(GameSet::Simulate),
+                )
+                    .run_if(in_state(AppState::MainMenu)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct MainMenu {
+    selected: usize,
+}
+
+/// Settings for the next match that the menu can change.
+#[derive(SystemParam)]
+struct MatchSetup<'w> {
+    lineup: ResMut<'w, Lineup>,
+    difficulty: ResMut<'w, Difficulty>,
+    rules: ResMut<'w, MatchRules>,
+    arena: ResMut<'w, Arena>,
+    settings: ResMut<'w, Settings>,
+}
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component)]
+struct MenuButton(usize);
+
+#[derive(Component)]
+struct MenuLabel(usize);
+
+fn setup(mut commands: Commands) {
+    commands
+        .spawn((
+            Name::new("Menu Root"),
+            MenuRoot,
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    row_gap: Val::Px(8.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn(
+                TextBundle::from_section(
+                    "PADDLE",
+                    TextStyle {
+                        font_size: 64.0,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    margin: UiRect::bottom(Val::Px(24.0)),
+                    ..default()
+                }),
+            );
+
+            for index in 0..ITEMS.len() {
+                c.spawn((
+                    MenuButton(index),
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(320.0),
+                            justify_content: JustifyContent::Center,
+                            padding: UiRect::all(Val::Px(6.0)),
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    },
+                ))
+                .with_children(|c| {
+                    c.spawn((
+                        MenuLabel(index),
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 24.0,
+                                ..default()
+                            },
+                        ),
+                    ));
+                });
+            }
+        });
+}
+
+fn cleanup(mut commands: Commands, roots: Query<Entity, With<MenuRoot>>) {
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Focus moves with the movement actions or the mouse, Confirm or a click
+/// picks the focused item and Back quits.
+fn navigate_menu(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    buttons: Query<(&MenuButton, &Interaction), Changed<Interaction>>,
+    mut menu: ResMut<MainMenu>,
+    mut next_match: MatchSetup,
+    mut start_events: EventWriter<StartMatch>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    if actions.just_pressed(Action::Back) {
+        app_exit_events.send(AppExit);
+        return;
+    }
+
+    if actions.just_pressed(Action::Controls) {
+        app_state_next_state.set(AppState::Controls);
+        return;
+    }
+
+    if actions.just_pressed(Action::WatchReplay) {
+        if watch_latest_replay(&mut commands) {
+            app_state_next_state.set(AppState::Replay);
+        }
+        return;
+    }
+
+    if actions.just_pressed(Action::MoveUp) {
+        menu.selected = (menu.selected + ITEMS.len() - 1) % ITEMS.len();
+    }
+    if actions.just_pressed(Action::MoveDown) {
+        menu.selected = (menu.selected + 1) % ITEMS.len();
+    }
+
+    let mut activated = actions.just_pressed(Action::Confirm);
+    for (button, interaction) in &buttons {
+        match interaction {
+            Interaction::Hovered => menu.selected = button.0,
+            Interaction::Pressed => {
+                menu.selected = button.0;
+                activated = true;
+            }
+            Interaction::None => {}
+        }
+    }
+
+    if !activated {
+        return;
+    }
+
+    match ITEMS[menu.selected].0 {
+        MenuItem::Play => {
+            start_events.send(StartMatch {
+                lineup: *next_match.lineup,
+                rules: next_match.rules.clone(),
+            });
+            app_state_next_state.set(AppState::Game);
+        }
+        MenuItem::Seat(id) => {
+            let seat = next_match.lineup.seat_mut(id);
+            *seat = seat.next();
+        }
+        MenuItem::Difficulty => *next_match.difficulty = next_match.difficulty.next(),
+        MenuItem::AdaptiveAi => {
+            next_match.settings.adaptive_ai ^= true;
+            if let Err(err) = next_match.settings.save() {
+                error!("Failed to save settings: {err}");
+            }
+        }
+        MenuItem::Rules => *next_match.rules = next_match.rules.next_preset(),
+        MenuItem::Doubles => next_match.rules.formation = next_match.rules.formation.next(),
+        MenuItem::Multiball => next_match.rules.multiball = next_match.rules.multiball.next(),
+        MenuItem::PowerUps => next_match.rules.power_ups ^= true,
+        MenuItem::Court => *next_match.arena = next_match.arena.next_preset(),
+        MenuItem::WatchReplay => {
+            if watch_latest_replay(&mut commands) {
+                app_state_next_state.set(AppState::Replay);
+            }
+        }
+        MenuItem::Controls => app_state_next_state.set(AppState::Controls),
+        MenuItem::Quit => app_exit_events.send(AppExit),
+    }
+}
+
+fn update_items(
+    menu: Res<MainMenu>,
+    lineup: Res<Lineup>,
+    rules: Res<MatchRules>,
+    arena: Res<Arena>,
+    difficulty: Res<Difficulty>,
+    settings: Res<Settings>,
+    mut buttons: Query<(&MenuButton, &mut BackgroundColor)>,
+    mut labels: Query<(&MenuLabel, &mut Text)>,
+) {
+    for (button, mut background) in &mut buttons {
+        *background = if button.0 == menu.selected {
+            SELECTED_BUTTON_COLOR.into()
+        } else {
+            BUTTON_COLOR.into()
+        };
+    }
+
+    for (label, mut text) in &mut labels {
+        let (item, name) = ITEMS[label.0];
+        let section = &mut text.sections[0];
+
+        section.value = match item {
+            MenuItem::Seat(id) => format!("{name}: {}", lineup.seat(id).name()),
+            MenuItem::Difficulty => format!("{name}: {}", difficulty.name()),
+            MenuItem::AdaptiveAi if settings.adaptive_ai => format!("{name}: On"),
+            MenuItem::AdaptiveAi => format!("{name}: Off"),
+            MenuItem::Rules => format!("{name}: {}", rules.name),
+            MenuItem::Doubles => format!("{name}: {}", rules.formation.name()),
+            MenuItem::Multiball => format!("{name}: {}", rules.multiball.name()),
+            MenuItem::PowerUps if rules.power_ups => format!("{name}: On"),
+            MenuItem::PowerUps => format!("{name}: Off"),
+            MenuItem::Court => format!("{name}: {}", arena.name),
+            _ => name.to_string(),
+        };
+        // teammates sit out in singles
+        let benched =
+            matches!(item, MenuItem::Seat(id) if id.slot >= rules.formation.paddles_per_side());
+        section.style.color = if label.0 == menu.selected {
+            Color::YELLOW
+        } else if benched {
+            Color::GRAY
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+/// How well the computer plays itself in the demo.
+const ATTRACT_DIFFICULTY: Difficulty = Difficulty::Normal;
+
+/// Puts a computer-vs-computer match behind the menu, started over whenever
+/// another court or other rules are picked. The match the player starts from the menu
+/// replaces it, so nothing needs to be restored.
+fn start_attract_mode(
+    arena: Res<Arena>,
+    rules: Res<MatchRules>,
+    power_ups: Res<PowerUps>,
+    mut game_rng: ResMut<GameRng>,
+    mut simulation: ResMut<Simulation>,
+    mut inputs: ResMut<PaddleInputs>,
+) {
+    let config = SimConfig::new(&arena).with_power_ups(power_ups.clone());
+    let sim = PongSim::new(config, rules.clone()).with_rng(game_rng.fork());
+    *simulation = Simulation::new(sim);
+    *inputs = PaddleInputs::default();
+}
+
+/// The demo plays on a court of its own, cleared away as soon as the menu is
+/// left for a match or a replay, with the computer on every paddle.
+fn spawn_attract_court(
+    mut court: CourtSpawner,
+    mut game_rng: ResMut<GameRng>,
+    simulation: Res<Simulation>,
+) {
+    let paddles = court.spawn(StateScoped(&[AppState::MainMenu]), &simulation.sim);
+
+    for paddle in paddles {
+        let ai = AI::new(ATTRACT_DIFFICULTY, game_rng.fork());
+        court.commands().entity(paddle).add(SetController(ai));
+    }
+}
+
+/// Keeps the demo going forever.
+fn restart_finished_demo(mut simulation: ResMut<Simulation>) {
+    if simulation.sim.winner.is_some() {
+        let config = simulation.sim.config.clone();
+        let rules = simulation.sim.rules.clone();
+        let rng = simulation.sim.rng.fork();
+        simulation.restart(config, rules, rng);
+    }
+}
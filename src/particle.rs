@@ -70,15 +70,24 @@ fn setup(mut commands: Commands, mut effects: ResMut<Assets<EffectAsset>>) {
             }),
     );
 
-    // Spawn an instance of the particle effect, and override its Z layer to
-    // be above the reference white square previously spawned.
-    commands
-        .spawn(ParticleEffectBundle {
+    commands.insert_resource(HitEffect(effect));
+}
+
+/// The effect played where the ball hits a paddle, spawned with every court.
+#[derive(Resource)]
+pub struct HitEffect(Handle<EffectAsset>);
+
+/// An instance of the particle effect, with its Z layer overridden to be
+/// above the court.
+pub fn hit_effect_bundle(effect: &HitEffect) -> impl Bundle {
+    (
+        ParticleEffectBundle {
             // Assign the Z layer so it appears in the egui inspector and can be modified at runtime
-            effect: ParticleEffect::new(effect).with_z_layer_2d(Some(0.1)),
+            effect: ParticleEffect::new(effect.0.clone()).with_z_layer_2d(Some(0.1)),
             ..default()
-        })
-        .insert(Name::new("effect:2d"));
+        },
+        Name::new("effect:2d"),
+    )
 }
 
 fn update(
@@ -0,0 +1,248 @@
+use bevy::{prelude::*, window::WindowFocused};
+
+use crate::{
+    actions::{Action, ActionState},
+    game::{Lineup, Simulation, StartMatch},
+    AppState,
+};
+
+/// Seconds counted down before play continues.
+const RESUME_COUNTDOWN: f32 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PauseOption {
+    Resume,
+    Restart,
+    QuitToMenu,
+}
+
+const OPTIONS: [(PauseOption, &str); 3] = [
+    (PauseOption::Resume, "Resume"),
+    (PauseOption::Restart, "Restart"),
+    (PauseOption::QuitToMenu, "Quit to Menu"),
+];
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseScreen>()
+            .add_systems(OnEnter(AppState::Paused), setup)
+            .add_systems(OnExit(AppState::Paused), cleanup)
+            .add_systems(
+                Update,
+                (
+                    pause_game.run_if(in_state(AppState::Game)),
+                    (choose_option, count_down, update_overlay)
+                        .chain()
+                        .run_if(in_state(AppState::Paused)),
+                ),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+struct PauseScreen {
+    selected: usize,
+    /// Running while play is about to continue; the options are hidden.
+    countdown: Option<Timer>,
+}
+
+#[derive(Component)]
+struct PauseRoot;
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct PauseOptionLabel(usize);
+
+#[derive(Component)]
+struct CountdownLabel;
+
+/// Pause on request, or as soon as the window loses focus.
+fn pause_game(
+    actions: Res<ActionState>,
+    mut focus_events: EventReader<WindowFocused>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    let focus_lost = focus_events.iter().any(|event| !event.focused);
+
+    if focus_lost || actions.just_pressed(Action::Pause) {
+        app_state_next_state.set(AppState::Paused);
+    }
+}
+
+fn setup(
+    mut commands: Commands,
+    mut screen: ResMut<PauseScreen>,
+    mut simulation: ResMut<Simulation>,
+) {
+    *screen = PauseScreen::default();
+    simulation.settle();
+
+    let text_style = TextStyle {
+        font_size: 24.0,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            Name::new("Pause Root"),
+            PauseRoot,
+            NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn((
+                PauseMenu,
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(8.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ))
+            .with_children(|c| {
+                c.spawn(TextBundle::from_section(
+                    "Paused",
+                    TextStyle {
+                        font_size: 48.0,
+                        ..default()
+                    },
+                ));
+
+                for (index, (_, label)) in OPTIONS.iter().enumerate() {
+                    c.spawn((
+                        PauseOptionLabel(index),
+                        TextBundle::from_section(*label, text_style.clone()),
+                    ));
+                }
+            });
+
+            c.spawn((
+                CountdownLabel,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 96.0,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+}
+
+fn cleanup(mut commands: Commands, roots: Query<Entity, With<PauseRoot>>) {
+    for entity in &roots {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn choose_option(
+    actions: Res<ActionState>,
+    mut screen: ResMut<PauseScreen>,
+    lineup: Res<Lineup>,
+    simulation: Res<Simulation>,
+    mut start_events: EventWriter<StartMatch>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    if screen.countdown.is_some() {
+        return;
+    }
+
+    if actions.just_pressed(Action::MoveUp) {
+        screen.selected = (screen.selected + OPTIONS.len() - 1) % OPTIONS.len();
+    }
+    if actions.just_pressed(Action::MoveDown) {
+        screen.selected = (screen.selected + 1) % OPTIONS.len();
+    }
+
+    let option = if actions.just_pressed(Action::Pause) || actions.just_pressed(Action::Back) {
+        PauseOption::Resume
+    } else if actions.just_pressed(Action::Confirm) {
+        OPTIONS[screen.selected].0
+    } else {
+        return;
+    };
+
+    match option {
+        PauseOption::Resume => {}
+        PauseOption::Restart => {
+            // the fresh match counts down to its own serve
+            start_events.send(StartMatch {
+                lineup: *lineup,
+                rules: simulation.sim.rules.clone(),
+            });
+            app_state_next_state.set(AppState::Game);
+            return;
+        }
+        PauseOption::QuitToMenu => {
+            app_state_next_state.set(AppState::MainMenu);
+            return;
+        }
+    }
+
+    screen.countdown = Some(Timer::from_seconds(RESUME_COUNTDOWN, TimerMode::Once));
+}
+
+fn count_down(
+    time: Res<Time>,
+    mut screen: ResMut<PauseScreen>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(countdown) = &mut screen.countdown else {
+        return;
+    };
+
+    if countdown.tick(time.delta()).finished() {
+        app_state_next_state.set(AppState::Game);
+    }
+}
+
+fn update_overlay(
+    screen: Res<PauseScreen>,
+    mut menus: Query<&mut Style, With<PauseMenu>>,
+    mut options: Query<(&PauseOptionLabel, &mut Text), Without<CountdownLabel>>,
+    mut countdowns: Query<&mut Text, With<CountdownLabel>>,
+) {
+    for mut style in &mut menus {
+        style.display = match screen.countdown {
+            Some(_) => Display::None,
+            None => Display::Flex,
+        };
+    }
+
+    for (option, mut text) in &mut options {
+        text.sections[0].style.color = if option.0 == screen.selected {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+    }
+
+    let seconds_left = screen
+        .countdown
+        .as_ref()
+        .map(|countdown| countdown.remaining_secs().ceil());
+
+    for mut text in &mut countdowns {
+        text.sections[0].value = match seconds_left {
+            Some(seconds) => format!("{seconds}"),
+            None => String::new(),
+        };
+    }
+}
@@ -0,0 +1,160 @@
+//! Power-ups on court, each glowing in its own color, and the shield walls
+//! they put up.
+
+use bevy::prelude::*;
+use bevy_magic_light_2d::prelude::*;
+
+use crate::ball::Ball;
+use crate::game::{GameSet, Simulation, SimulationEvent, StateScoped};
+use crate::particle::SpawnParticle;
+use crate::powerup::{PowerUpKind, PowerUps};
+use crate::sim::{Pickup, PickupId, Side, SimEvent};
+
+/// How fast a power-up turns on the spot, in radians per second.
+const PICKUP_SPIN_RATE: f32 = 1.5;
+/// Thickness of a shield wall.
+const SHIELD_WIDTH: f32 = 2.0;
+
+pub struct PickupPlugin;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PowerUps::load_or_default())
+            .add_systems(
+                Update,
+                (match_pickups, spin_pickups, match_shields, collect_pickups)
+                    .chain()
+                    .in_set(GameSet::Sync),
+            );
+    }
+}
+
+/// Marks a power-up drawn on court.
+#[derive(Component)]
+struct PowerUpSprite;
+
+/// The wall across `side`'s goal while its shield is up.
+#[derive(Component)]
+struct ShieldWall(Side);
+
+fn pickup_bundle(pickup: &Pickup, size: f32) -> impl Bundle {
+    (
+        Name::new(format!("{} Power-up", pickup.kind.name())),
+        PowerUpSprite,
+        pickup.id,
+        SpriteBundle {
+            transform: Transform::from_translation(pickup.position.extend(0.0)),
+            sprite: Sprite {
+                color: pickup.kind.color(),
+                custom_size: Some(Vec2::splat(size)),
+                ..default()
+            },
+            ..default()
+        },
+        OmniLightSource2D {
+            intensity: 0.1,
+            color: pickup.kind.color(),
+            falloff: Vec3::new(0.15, 0.25, 0.005),
+            ..default()
+        },
+    )
+}
+
+/// Keeps one entity per power-up on court: spawns the ones just dropped and
+/// despawns the ones collected or left too long. New ones join the court
+/// the balls belong to.
+fn match_pickups(
+    mut commands: Commands,
+    simulation: Res<Simulation>,
+    balls: Query<&StateScoped, With<Ball>>,
+    pickups: Query<(Entity, &PickupId), With<PowerUpSprite>>,
+) {
+    let Some(&scope) = balls.iter().next() else {
+        return;
+    };
+    let sim = &simulation.sim;
+
+    for (entity, id) in &pickups {
+        if !sim.pickups.iter().any(|pickup| pickup.id == *id) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for pickup in &sim.pickups {
+        if pickups.iter().any(|(_, id)| *id == pickup.id) {
+            continue;
+        }
+        commands.spawn((pickup_bundle(pickup, sim.config.power_ups.size), scope));
+    }
+}
+
+fn spin_pickups(time: Res<Time>, mut pickups: Query<&mut Transform, With<PowerUpSprite>>) {
+    for mut transform in &mut pickups {
+        transform.rotate_z(PICKUP_SPIN_RATE * time.delta_seconds());
+    }
+}
+
+/// Puts a glowing wall across each goal while its side has a shield, and
+/// takes it down again once the shield runs out.
+fn match_shields(
+    mut commands: Commands,
+    simulation: Res<Simulation>,
+    balls: Query<&StateScoped, With<Ball>>,
+    walls: Query<(Entity, &ShieldWall)>,
+) {
+    let Some(&scope) = balls.iter().next() else {
+        return;
+    };
+    let sim = &simulation.sim;
+
+    for side in Side::ALL {
+        let shielded = sim.has_effect(side, PowerUpKind::Shield);
+        let wall = walls.iter().find(|(_, wall)| wall.0 == side);
+
+        match (shielded, wall) {
+            (true, None) => {
+                let color = PowerUpKind::Shield.color();
+                let x = -side.forward() * (sim.config.goal_x - SHIELD_WIDTH / 2.0);
+                commands.spawn((
+                    Name::new("Shield"),
+                    ShieldWall(side),
+                    SpriteBundle {
+                        transform: Transform::from_xyz(x, 0.0, 0.0),
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(Vec2::new(SHIELD_WIDTH, 2.0 * sim.config.wall_y)),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    OmniLightSource2D {
+                        intensity: 0.05,
+                        color,
+                        falloff: Vec3::new(0.15, 0.25, 0.005),
+                        ..default()
+                    },
+                    scope,
+                ));
+            }
+            (false, Some((entity, _))) => commands.entity(entity).despawn_recursive(),
+            _ => {}
+        }
+    }
+}
+
+/// Sparks where a power-up is collected.
+fn collect_pickups(
+    mut sim_events: EventReader<SimulationEvent>,
+    mut particle_event: EventWriter<SpawnParticle>,
+) {
+    for event in sim_events.iter() {
+        let SimEvent::PowerUpCollected { position, .. } = event.0 else {
+            continue;
+        };
+
+        particle_event.send(SpawnParticle {
+            position: position.extend(0.0),
+            rotation: Quat::IDENTITY,
+        });
+    }
+}
@@ -1,68 +1,72 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::{prelude::ReflectInspectorOptions, InspectorOptions};
-use bevy_magic_light_2d::prelude::{LightOccluder2D, OmniLightSource2D};
 
-use crate::{AppState, Paddle, Score};
+use crate::{
+    controller::{AppControllerExt, ControlContext, PaddleController},
+    sim::PaddleInput,
+};
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<Player>()
-            .add_systems(Startup, setup)
-            .add_systems(Update, player_controller.run_if(in_state(AppState::Game)));
+            .register_type::<Keys>()
+            .add_paddle_controller::<Player>();
     }
 }
 
-fn setup(mut commands: Commands) {
-    commands.spawn((
-        Name::new("Left Paddle"),
-        Paddle { speed: 100.0 },
-        Player,
-        Score::default(),
-        SpriteBundle {
-            transform: Transform::from_translation(Vec3::new(-100.0, 0.0, 0.0)),
-            sprite: Sprite {
-                color: Color::WHITE,
-                custom_size: Some(Vec2::new(4.0, 16.0)),
-                anchor: default(),
-                ..default()
-            },
-            ..Default::default()
-        },
-        OmniLightSource2D {
-            intensity: 0.2,
-            color: Color::rgb_u8(28, 28, 255),
-            falloff: Vec3::new(0.15, 0.25, 0.005),
-            ..default()
-        },
-        LightOccluder2D {
-            h_size: Vec2::new(4.0, 16.0),
-        },
-    ));
+#[derive(Component, InspectorOptions, Default, Reflect)]
+#[reflect(Component, InspectorOptions)]
+pub struct Player {
+    pub keys: Keys,
+    /// Assigned automatically when a gamepad is plugged in.
+    pub gamepad: Option<Gamepad>,
 }
 
-fn player_controller(
-    keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&Paddle, &mut Transform), With<Player>>,
-    time: Res<Time>,
-) {
-    for (paddle, mut transform) in &mut query {
-        let speed = paddle.speed * time.delta_seconds();
+impl PaddleController for Player {
+    /// Follows whichever of the player's seats is pushed hardest, and serves
+    /// or dashes when any of them asks to.
+    fn next_input(&mut self, ctx: &ControlContext) -> PaddleInput {
+        let axis = self
+            .keys
+            .seats()
+            .iter()
+            .map(|&seat| ctx.actions.movement(seat))
+            .fold(0.0, |axis: f32, seat_axis| {
+                if seat_axis.abs() > axis.abs() {
+                    seat_axis
+                } else {
+                    axis
+                }
+            });
 
-        if (keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up))
-            && transform.translation.y < 64.0
-        {
-            transform.translation.y += speed;
-        }
-        if (keyboard_input.pressed(KeyCode::S) || keyboard_input.pressed(KeyCode::Down))
-            && transform.translation.y > -64.0
-        {
-            transform.translation.y -= speed;
-        }
+        let seats = self.keys.seats().iter();
+        let serve = seats.clone().any(|&seat| ctx.actions.serving(seat));
+        let dash = seats.clone().any(|&seat| ctx.actions.dashing(seat));
+
+        PaddleInput::new(axis).serving(serve).dashing(dash)
     }
 }
 
-#[derive(Component, InspectorOptions, Default, Reflect)]
-#[reflect(Component, InspectorOptions)]
-pub struct Player;
+/// Whose bindings in [`InputBindings`](crate::actions::InputBindings) a
+/// player steers with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+pub enum Keys {
+    /// Both players' bindings, for playing alone.
+    #[default]
+    Any,
+    First,
+    Second,
+}
+
+impl Keys {
+    /// Indices into [`InputBindings::players`](crate::actions::InputBindings::players).
+    pub fn seats(self) -> &'static [usize] {
+        match self {
+            Keys::Any => &[0, 1],
+            Keys::First => &[0],
+            Keys::Second => &[1],
+        }
+    }
+}
@@ -0,0 +1,436 @@
+//! Collectible power-ups and what they do.
+//!
+//! [`PowerUps`] lists the power-ups that can appear on court, how likely each
+//! one is and how long its effect lasts. It is read from [`PowerUps::PATH`] at
+//! startup. With [`MatchRules::power_ups`] on, the simulation drops them onto
+//! the court. A ball running through one hands its [`Effect`] to the side that
+//! hit the ball last, or turns it against that side's opponent.
+//!
+//! [`MatchRules::power_ups`]: crate::rules::MatchRules::power_ups
+
+use std::{fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::Side;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    /// Taller paddles for the collector.
+    Grow,
+    /// Shorter paddles for the collector's opponent.
+    Shrink,
+    /// Faster paddles for the collector.
+    SpeedBoost,
+    /// Every ball on court slows down.
+    SlowBall,
+    /// The collector's paddles catch the ball and serve it back when told.
+    Sticky,
+    /// A wall across the collector's goal that bounces balls back out.
+    Shield,
+    /// Up and down swap for the collector's opponent.
+    ReverseControls,
+    /// The ball that collected it splits on the spot.
+    Multiball,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 8] = [
+        PowerUpKind::Grow,
+        PowerUpKind::Shrink,
+        PowerUpKind::SpeedBoost,
+        PowerUpKind::SlowBall,
+        PowerUpKind::Sticky,
+        PowerUpKind::Shield,
+        PowerUpKind::ReverseControls,
+        PowerUpKind::Multiball,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUpKind::Grow => "Grow",
+            PowerUpKind::Shrink => "Shrink",
+            PowerUpKind::SpeedBoost => "Speed",
+            PowerUpKind::SlowBall => "Slow Ball",
+            PowerUpKind::Sticky => "Sticky",
+            PowerUpKind::Shield => "Shield",
+            PowerUpKind::ReverseControls => "Reverse",
+            PowerUpKind::Multiball => "Multiball",
+        }
+    }
+
+    /// Glow of the pickup on court and of its indicator in the HUD.
+    pub fn color(self) -> Color {
+        match self {
+            PowerUpKind::Grow => Color::GREEN,
+            PowerUpKind::Shrink => Color::ORANGE_RED,
+            PowerUpKind::SpeedBoost => Color::YELLOW,
+            PowerUpKind::SlowBall => Color::CYAN,
+            PowerUpKind::Sticky => Color::FUCHSIA,
+            PowerUpKind::Shield => Color::ALICE_BLUE,
+            PowerUpKind::ReverseControls => Color::PURPLE,
+            PowerUpKind::Multiball => Color::GOLD,
+        }
+    }
+
+    /// The side the effect of a power-up collected by `collector` acts on.
+    pub fn target(self, collector: Side) -> Side {
+        match self {
+            PowerUpKind::Shrink | PowerUpKind::ReverseControls => collector.opponent(),
+            _ => collector,
+        }
+    }
+}
+
+/// What collecting another power-up of a kind does while its effect is
+/// still running on the same side.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stacking {
+    /// Starts the running effect's time over.
+    Refresh,
+    /// Adds the full duration to the running effect's time left.
+    Extend,
+    /// Runs alongside it, up to `max` at once. Past that, the one closest
+    /// to running out starts over instead.
+    Stack { max: u32 },
+}
+
+/// How one kind of power-up behaves.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PowerUpSpec {
+    pub kind: PowerUpKind,
+    /// Chance of being the one dropped, relative to the other kinds; zero
+    /// never drops it.
+    pub weight: f32,
+    /// Seconds the effect lasts.
+    pub duration: f32,
+    /// Strength of the effect. It is the height factor for Grow and Shrink,
+    /// the speed factor for SpeedBoost and SlowBall, seconds a ball stays
+    /// caught for Sticky and the balls split into for Multiball.
+    pub amount: f32,
+    pub stacking: Stacking,
+}
+
+/// When power-ups drop, and the table of every kind that can.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerUps {
+    /// Seconds of play between drops.
+    pub spawn_every: f32,
+    /// Most power-ups waiting on court at once.
+    pub max_on_court: u32,
+    /// Seconds a power-up waits to be collected before it disappears.
+    pub lifetime: f32,
+    /// Width and height of a power-up.
+    pub size: f32,
+    pub kinds: Vec<PowerUpSpec>,
+}
+
+impl Default for PowerUps {
+    fn default() -> Self {
+        let spec = |kind, weight, duration, amount, stacking| PowerUpSpec {
+            kind,
+            weight,
+            duration,
+            amount,
+            stacking,
+        };
+
+        Self {
+            spawn_every: 8.0,
+            max_on_court: 2,
+            lifetime: 10.0,
+            size: 8.0,
+            kinds: vec![
+                spec(
+                    PowerUpKind::Grow,
+                    3.0,
+                    10.0,
+                    1.5,
+                    Stacking::Stack { max: 2 },
+                ),
+                spec(
+                    PowerUpKind::Shrink,
+                    2.0,
+                    8.0,
+                    0.6,
+                    Stacking::Stack { max: 2 },
+                ),
+                spec(PowerUpKind::SpeedBoost, 3.0, 8.0, 1.5, Stacking::Refresh),
+                spec(PowerUpKind::SlowBall, 2.0, 6.0, 0.6, Stacking::Extend),
+                spec(PowerUpKind::Sticky, 2.0, 10.0, 1.5, Stacking::Refresh),
+                spec(PowerUpKind::Shield, 1.0, 6.0, 0.0, Stacking::Extend),
+                spec(
+                    PowerUpKind::ReverseControls,
+                    1.0,
+                    5.0,
+                    0.0,
+                    Stacking::Refresh,
+                ),
+                spec(PowerUpKind::Multiball, 1.0, 0.0, 3.0, Stacking::Refresh),
+            ],
+        }
+    }
+}
+
+impl PowerUps {
+    pub const PATH: &'static str = "assets/powerups.ron";
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// The table at [`PowerUps::PATH`], or the built-in one if it can't be
+    /// read.
+    pub fn load_or_default() -> Self {
+        Self::load(Self::PATH).unwrap_or_else(|err| {
+            warn!(
+                "Using the built-in power-ups, failed to read {}: {err}",
+                Self::PATH
+            );
+            Self::default()
+        })
+    }
+
+    pub fn spec(&self, kind: PowerUpKind) -> Option<&PowerUpSpec> {
+        self.kinds.iter().find(|spec| spec.kind == kind)
+    }
+
+    /// The kind a `roll` in `0.0..1.0` lands on, by weight.
+    pub fn pick(&self, roll: f32) -> Option<&PowerUpSpec> {
+        let total: f32 = self.kinds.iter().map(|spec| spec.weight.max(0.0)).sum();
+        let mut left = roll * total;
+
+        self.kinds
+            .iter()
+            .filter(|spec| spec.weight > 0.0)
+            .find(|spec| {
+                left -= spec.weight;
+                left < 0.0
+            })
+            .or_else(|| self.kinds.iter().rfind(|spec| spec.weight > 0.0))
+    }
+}
+
+/// A power-up's effect running on one side.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Effect {
+    pub kind: PowerUpKind,
+    /// The side it acts on.
+    pub side: Side,
+    /// [`PowerUpSpec::amount`] of the power-up that started it.
+    pub amount: f32,
+    /// Seconds left.
+    pub remaining: f32,
+}
+
+/// Starts `spec`'s effect on `side` among the running `effects`, following
+/// its [`Stacking`].
+pub fn add_effect(effects: &mut Vec<Effect>, spec: &PowerUpSpec, side: Side) {
+    let effect = Effect {
+        kind: spec.kind,
+        side,
+        amount: spec.amount,
+        remaining: spec.duration,
+    };
+    let mut running = effects
+        .iter_mut()
+        .filter(|running| running.kind == spec.kind && running.side == side);
+
+    match spec.stacking {
+        Stacking::Refresh => match running.next() {
+            Some(running) => *running = effect,
+            None => effects.push(effect),
+        },
+        Stacking::Extend => match running.next() {
+            Some(running) => running.remaining += spec.duration,
+            None => effects.push(effect),
+        },
+        Stacking::Stack { max } => {
+            let count = running.count() as u32;
+            if count < max.max(1) {
+                effects.push(effect);
+            } else if let Some(oldest) = effects
+                .iter_mut()
+                .filter(|running| running.kind == spec.kind && running.side == side)
+                .min_by(|a, b| a.remaining.total_cmp(&b.remaining))
+            {
+                *oldest = effect;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{
+        tests::in_play, BallId, BallState, PaddleId, PaddleInput, Pickup, PickupId, SimEvent,
+    };
+
+    #[test]
+    fn power_ups_go_to_whoever_hit_the_ball_last() {
+        let mut sim = in_play();
+        let pickup = |id, kind, position| Pickup {
+            id: PickupId(id),
+            kind,
+            position,
+            remaining: 10.0,
+        };
+        sim.pickups = vec![
+            pickup(0, PowerUpKind::Grow, Vec2::new(5.0, 20.0)),
+            pickup(1, PowerUpKind::Shrink, Vec2::new(15.0, 20.0)),
+            pickup(2, PowerUpKind::SpeedBoost, Vec2::new(10.0, -20.0)),
+        ];
+        sim.balls[0].position = Vec2::new(0.0, 20.0);
+        sim.balls[0].velocity = Vec2::new(60.0, 0.0);
+        sim.balls[0].last_hit = Some(PaddleId::from(Side::Left));
+        // nobody has hit this one yet
+        sim.balls.push(BallState {
+            id: BallId(1),
+            position: Vec2::new(0.0, -20.0),
+            last_hit: None,
+            ..sim.balls[0].clone()
+        });
+
+        let mut collected = Vec::new();
+        for _ in 0..60 {
+            for event in sim.step(&[], 1.0 / 120.0) {
+                if let SimEvent::PowerUpCollected { kind, side, .. } = event {
+                    collected.push((kind, side));
+                }
+            }
+        }
+
+        assert_eq!(
+            collected,
+            [
+                (PowerUpKind::Grow, Side::Left),
+                (PowerUpKind::Shrink, Side::Left)
+            ]
+        );
+        let ids: Vec<_> = sim.pickups.iter().map(|pickup| pickup.id).collect();
+        assert_eq!(ids, [PickupId(2)]);
+
+        // shrinking hits the collector's opponent
+        let height = sim.config.paddle_size.y;
+        assert!(sim.has_effect(Side::Left, PowerUpKind::Grow));
+        assert!(sim.has_effect(Side::Right, PowerUpKind::Shrink));
+        assert!((sim.paddle(Side::Left).size.y - height * 1.5).abs() < 1e-3);
+        assert!((sim.paddle(Side::Right).size.y - height * 0.6).abs() < 1e-3);
+    }
+
+    #[test]
+    fn power_ups_stack_extend_or_refresh_by_kind() {
+        let spec = |stacking| PowerUpSpec {
+            kind: PowerUpKind::Grow,
+            weight: 1.0,
+            duration: 10.0,
+            amount: 1.5,
+            stacking,
+        };
+        let remaining = |effects: &[Effect]| -> Vec<f32> {
+            effects.iter().map(|effect| effect.remaining).collect()
+        };
+
+        let stack = spec(Stacking::Stack { max: 2 });
+        let mut effects = Vec::new();
+        add_effect(&mut effects, &stack, Side::Left);
+        effects[0].remaining = 4.0;
+        add_effect(&mut effects, &stack, Side::Left);
+        effects[1].remaining = 6.0;
+        // past the cap, the one closest to running out starts over
+        add_effect(&mut effects, &stack, Side::Left);
+        assert_eq!(remaining(&effects), [10.0, 6.0]);
+        add_effect(&mut effects, &stack, Side::Right);
+        assert_eq!(effects.len(), 3);
+
+        for (stacking, expected) in [(Stacking::Extend, 14.0), (Stacking::Refresh, 10.0)] {
+            let mut effects = Vec::new();
+            add_effect(&mut effects, &spec(stacking), Side::Left);
+            effects[0].remaining = 4.0;
+            add_effect(&mut effects, &spec(stacking), Side::Left);
+            assert_eq!(remaining(&effects), [expected]);
+        }
+    }
+
+    #[test]
+    fn a_shield_keeps_the_ball_out_of_goal_until_it_runs_out() {
+        let mut sim = in_play();
+        let goal = sim.config.goal_x;
+        sim.effects.push(Effect {
+            kind: PowerUpKind::Shield,
+            side: Side::Left,
+            amount: 0.0,
+            remaining: 0.3,
+        });
+        sim.balls[0].position = Vec2::new(-goal + 3.0, 60.0);
+        sim.balls[0].velocity = Vec2::new(-100.0, 0.0);
+
+        let mut blocked = false;
+        for _ in 0..30 {
+            for event in sim.step(&[], 1.0 / 120.0) {
+                assert!(!matches!(event, SimEvent::Goal { .. }));
+                blocked |= event
+                    == SimEvent::WallBounce {
+                        contact: Vec2::new(-goal, 60.0),
+                        normal: Vec2::X,
+                    };
+            }
+        }
+        assert!(blocked);
+        assert!(sim.balls[0].velocity.x > 0.0);
+
+        sim.balls[0].velocity.x *= -1.0;
+        let goal_scored = (0..120).any(|_| {
+            sim.step(&[], 1.0 / 120.0)
+                .iter()
+                .any(|event| matches!(event, SimEvent::Goal { .. }))
+        });
+        assert!(goal_scored);
+        assert!(sim.effects.is_empty());
+    }
+
+    #[test]
+    fn a_sticky_paddle_holds_the_ball_until_served() {
+        let mut sim = in_play();
+        sim.effects.push(Effect {
+            kind: PowerUpKind::Sticky,
+            side: Side::Left,
+            amount: 5.0,
+            remaining: 10.0,
+        });
+        sim.balls[0].position = Vec2::new(-80.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(-100.0, 0.0);
+
+        while !sim
+            .step(&[], 1.0 / 120.0)
+            .iter()
+            .any(|e| matches!(e, SimEvent::PaddleHit { .. }))
+        {}
+        assert!(sim.balls[0].held.is_some());
+        let speed = sim.balls[0].velocity.length();
+        let x = sim.balls[0].position.x;
+
+        // carried along with the paddle
+        let up = [PaddleInput::new(1.0)];
+        for _ in 0..10 {
+            sim.step(&up, 1.0 / 120.0);
+        }
+        let ball = &sim.balls[0];
+        let paddle = sim.paddle(Side::Left);
+        assert!(ball.held.is_some());
+        assert!(paddle.position.y > 0.0);
+        assert!((ball.position.y - paddle.position.y).abs() < 1e-3);
+        assert!((ball.position.x - x).abs() < 1e-3);
+
+        // and served back aimed by the controls, as fast as it came in
+        sim.step(&[PaddleInput::new(1.0).serving(true)], 1.0 / 120.0);
+        let ball = &sim.balls[0];
+        assert!(ball.held.is_none());
+        assert!(ball.velocity.x > 0.0 && ball.velocity.y > 0.0);
+        assert!((ball.velocity.length() - speed).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,1089 @@
+//! Match recording and deterministic playback.
+//!
+//! The simulation only depends on its starting configuration, the paddle
+//! inputs of every tick and any paddle tuning made from the inspector along
+//! the way, so that is all a replay stores. Playback feeds them back through
+//! the same [`PongSim`] to reproduce the match.
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use bevy::prelude::*;
+
+use crate::{
+    actions::{Action, ActionState},
+    arena::Arena,
+    cli::CliArgs,
+    controller::{AppControllerExt, ControlContext, PaddleController, SetController},
+    game::{
+        start_match, step_simulation, CourtSpawner, GameSet, PaddleInputs, Simulation,
+        SimulationEvent, StartMatch, StateScoped,
+    },
+    powerup::{PowerUpKind, PowerUpSpec, PowerUps, Stacking},
+    rng::Rng,
+    rules::{
+        Formation, MatchRules, Multiball, PaddleMotion, ServeFrom, ServeRule, SpeedCurve,
+        SpeedModel,
+    },
+    sim::{PaddleId, PaddleInput, PhysicsConfig, PongSim, SimConfig, SimEvent, MAX_PADDLES},
+    AppState,
+};
+
+const MAGIC: &[u8; 4] = b"PDLR";
+const FORMAT_VERSION: u8 = 14;
+
+/// Where the most recent match is written to.
+const LATEST_REPLAY: &str = "replays/latest.replay";
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_paddle_controller::<Playback>()
+            .add_systems(Startup, play_from_command_line)
+            .add_systems(
+                OnEnter(AppState::Game),
+                start_recording
+                    .after(start_match)
+                    .run_if(on_event::<StartMatch>()),
+            )
+            .add_systems(
+                FixedUpdate,
+                record_inputs
+                    .after(GameSet::Input)
+                    .before(GameSet::Simulate)
+                    .run_if(in_state(AppState::Game)),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), save_abandoned_match)
+            .add_systems(
+                Update,
+                (
+                    save_finished_match.run_if(in_state(AppState::Game)),
+                    (playback_controls, update_playback_label).run_if(in_state(AppState::Replay)),
+                ),
+            )
+            .add_systems(
+                OnEnter(AppState::Replay),
+                (begin_playback, spawn_replay_court).chain(),
+            )
+            .add_systems(OnExit(AppState::Replay), end_playback)
+            .add_systems(
+                FixedUpdate,
+                (
+                    advance_playback.in_set(GameSet::Input),
+                    step_simulation.in_set(GameSet::Simulate),
+                )
+                    .run_if(in_state(AppState::Replay).and_then(has_inputs_left)),
+            );
+    }
+}
+
+/// Everything needed to rebuild the simulation a replay started from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayHeader {
+    pub game_version: String,
+    pub tick_rate: f32,
+    /// What the match's randomness was seeded with.
+    pub seed: u64,
+    pub arena: Arena,
+    pub config: SimConfig,
+    pub rules: MatchRules,
+    /// Speed of each paddle in play, indexed by
+    /// [`PaddleId::index`](crate::sim::PaddleId::index).
+    pub paddle_speeds: Vec<f32>,
+    /// Motion of each paddle in play, which may have been tuned away from
+    /// the rules.
+    pub paddle_motions: Vec<PaddleMotion>,
+}
+
+impl ReplayHeader {
+    pub fn new(sim: &PongSim, arena: &Arena, tick_rate: f32) -> Self {
+        Self {
+            game_version: env!("CARGO_PKG_VERSION").to_string(),
+            tick_rate,
+            seed: sim.rng.seed(),
+            arena: arena.clone(),
+            config: sim.config.clone(),
+            rules: sim.rules.clone(),
+            paddle_speeds: sim.paddles.iter().map(|paddle| paddle.speed).collect(),
+            paddle_motions: sim.paddles.iter().map(|paddle| paddle.motion).collect(),
+        }
+    }
+
+    /// The simulation as it was on the first recorded tick.
+    pub fn initial_sim(&self) -> PongSim {
+        let mut sim =
+            PongSim::new(self.config.clone(), self.rules.clone()).with_rng(Rng::new(self.seed));
+        let tuning = self.paddle_speeds.iter().zip(&self.paddle_motions);
+        for (paddle, (&speed, &motion)) in sim.paddles.iter_mut().zip(tuning) {
+            paddle.speed = speed;
+            paddle.motion = motion;
+        }
+        sim
+    }
+}
+
+/// A paddle retuned partway through a match.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Retune {
+    /// Index into [`Replay::inputs`] of the first tick played with it.
+    pub tick: u32,
+    /// [`PaddleId::index`](crate::sim::PaddleId::index) of the paddle.
+    pub paddle: u8,
+    pub speed: f32,
+    pub motion: PaddleMotion,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub header: ReplayHeader,
+    /// Paddle inputs of every tick, in order.
+    pub inputs: Vec<[PaddleInput; MAX_PADDLES]>,
+    /// Paddle tuning changed since the header, in tick order.
+    pub tuning: Vec<Retune>,
+}
+
+impl Replay {
+    pub fn new(header: ReplayHeader) -> Self {
+        Self {
+            header,
+            inputs: Vec::new(),
+            tuning: Vec::new(),
+        }
+    }
+
+    /// Appends a tick played with `inputs`, first noting any paddle of `sim`
+    /// tuned differently since the last tick.
+    pub fn record(&mut self, sim: &PongSim, inputs: [PaddleInput; MAX_PADDLES]) {
+        let tick = self.inputs.len() as u32;
+
+        for (index, paddle) in sim.paddles.iter().enumerate() {
+            if self.tuning_of(index) != Some((paddle.speed, paddle.motion)) {
+                self.tuning.push(Retune {
+                    tick,
+                    paddle: index as u8,
+                    speed: paddle.speed,
+                    motion: paddle.motion,
+                });
+            }
+        }
+
+        self.inputs.push(inputs);
+    }
+
+    /// Retunes the paddles of `sim` that were changed on `tick`.
+    pub fn apply_tuning(&self, tick: usize, sim: &mut PongSim) {
+        for retune in self
+            .tuning
+            .iter()
+            .filter(|retune| retune.tick as usize == tick)
+        {
+            if let Some(paddle) = sim.paddles.get_mut(retune.paddle as usize) {
+                paddle.speed = retune.speed;
+                paddle.motion = retune.motion;
+            }
+        }
+    }
+
+    /// Speed and motion of paddle `index` as of the last recorded tick.
+    fn tuning_of(&self, index: usize) -> Option<(f32, PaddleMotion)> {
+        let header = &self.header;
+        self.tuning
+            .iter()
+            .rfind(|retune| retune.paddle as usize == index)
+            .map(|retune| (retune.speed, retune.motion))
+            .or_else(|| {
+                Some((
+                    *header.paddle_speeds.get(index)?,
+                    *header.paddle_motions.get(index)?,
+                ))
+            })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes the header followed by the inputs, run-length encoded since
+    /// paddles are held still or moved in one direction for many ticks, and
+    /// then the tuning changes. Only the paddles in play are written, their
+    /// serve buttons in the low bits of a run's button byte and their dash
+    /// buttons from bit 4 up.
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        let header = &self.header;
+        let config = &header.config;
+
+        w.write_all(MAGIC)?;
+        w.write_all(&[FORMAT_VERSION])?;
+        write_str(w, &header.game_version)?;
+        write_f32(w, header.tick_rate)?;
+        w.write_all(&header.seed.to_le_bytes())?;
+
+        write_arena(w, &header.arena)?;
+        write_physics(w, &config.physics)?;
+        write_power_ups(w, &config.power_ups)?;
+        write_rules(w, &header.rules)?;
+
+        let paddles = paddle_count(&header.rules);
+        if header.paddle_speeds.len() != paddles || header.paddle_motions.len() != paddles {
+            return Err(invalid_data("paddles don't match the formation"));
+        }
+        for &speed in &header.paddle_speeds {
+            write_f32(w, speed)?;
+        }
+        for motion in &header.paddle_motions {
+            write_motion(w, motion)?;
+        }
+
+        let runs = run_lengths(&self.inputs);
+        w.write_all(&(runs.len() as u32).to_le_bytes())?;
+        for (count, inputs) in runs {
+            let mut buttons = 0;
+            w.write_all(&count.to_le_bytes())?;
+            for (index, input) in inputs[..paddles].iter().enumerate() {
+                buttons |= (input.serve as u8) << index | (input.dash as u8) << (index + 4);
+                w.write_all(&[input.to_i8() as u8])?;
+            }
+            w.write_all(&[buttons])?;
+        }
+
+        w.write_all(&(self.tuning.len() as u32).to_le_bytes())?;
+        for retune in &self.tuning {
+            if retune.paddle as usize >= paddles {
+                return Err(invalid_data("tuning for a paddle not in play"));
+            }
+            w.write_all(&retune.tick.to_le_bytes())?;
+            w.write_all(&[retune.paddle])?;
+            write_f32(w, retune.speed)?;
+            write_motion(w, &retune.motion)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read(r: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid_data("not a replay file"));
+        }
+
+        let format = read_u8(r)?;
+        if format != FORMAT_VERSION {
+            return Err(invalid_data(format!("unsupported replay format {format}")));
+        }
+
+        let game_version = read_str(r)?;
+        let tick_rate = read_f32(r)?;
+        let seed = read_u64(r)?;
+
+        let arena = read_arena(r)?;
+        let config = SimConfig {
+            physics: read_physics(r)?,
+            power_ups: read_power_ups(r)?,
+            ..SimConfig::new(&arena)
+        };
+        let rules = read_rules(r)?;
+        let paddles = paddle_count(&rules);
+        let paddle_speeds = (0..paddles)
+            .map(|_| read_f32(r))
+            .collect::<io::Result<_>>()?;
+        let paddle_motions = (0..paddles)
+            .map(|_| read_motion(r))
+            .collect::<io::Result<_>>()?;
+
+        let mut inputs = Vec::new();
+        for _ in 0..read_u32(r)? {
+            let count = read_u16(r)?;
+            let mut tick = [PaddleInput::IDLE; MAX_PADDLES];
+            for input in &mut tick[..paddles] {
+                *input = PaddleInput::from_i8(read_u8(r)? as i8);
+            }
+            let buttons = read_u8(r)?;
+            for (index, input) in tick[..paddles].iter_mut().enumerate() {
+                *input = input
+                    .serving(buttons & 1 << index != 0)
+                    .dashing(buttons & 1 << (index + 4) != 0);
+            }
+            inputs.extend(std::iter::repeat(tick).take(count as usize));
+        }
+
+        let tuning = (0..read_u32(r)?)
+            .map(|_| {
+                let tick = read_u32(r)?;
+                let paddle = read_u8(r)?;
+                if paddle as usize >= paddles {
+                    return Err(invalid_data(format!("tuning for unknown paddle {paddle}")));
+                }
+                Ok(Retune {
+                    tick,
+                    paddle,
+                    speed: read_f32(r)?,
+                    motion: read_motion(r)?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            header: ReplayHeader {
+                game_version,
+                tick_rate,
+                seed,
+                arena,
+                config,
+                rules,
+                paddle_speeds,
+                paddle_motions,
+            },
+            inputs,
+            tuning,
+        })
+    }
+}
+
+/// Paddles in play under `rules`, and so recorded on every tick.
+fn paddle_count(rules: &MatchRules) -> usize {
+    2 * rules.formation.paddles_per_side()
+}
+
+fn run_lengths(inputs: &[[PaddleInput; MAX_PADDLES]]) -> Vec<(u16, [PaddleInput; MAX_PADDLES])> {
+    let mut runs: Vec<(u16, [PaddleInput; MAX_PADDLES])> = Vec::new();
+
+    for &input in inputs {
+        match runs.last_mut() {
+            Some((count, last)) if *last == input && *count < u16::MAX => *count += 1,
+            _ => runs.push((1, input)),
+        }
+    }
+
+    runs
+}
+
+fn write_arena(w: &mut impl Write, arena: &Arena) -> io::Result<()> {
+    write_str(w, &arena.name)?;
+    for value in [
+        arena.size.x,
+        arena.size.y,
+        arena.ball_size.x,
+        arena.ball_size.y,
+        arena.paddle_size.x,
+        arena.paddle_size.y,
+        arena.paddle_inset,
+    ] {
+        write_f32(w, value)?;
+    }
+    Ok(())
+}
+
+fn read_arena(r: &mut impl Read) -> io::Result<Arena> {
+    Ok(Arena {
+        name: read_str(r)?,
+        size: Vec2::new(read_f32(r)?, read_f32(r)?),
+        ball_size: Vec2::new(read_f32(r)?, read_f32(r)?),
+        paddle_size: Vec2::new(read_f32(r)?, read_f32(r)?),
+        paddle_inset: read_f32(r)?,
+    })
+}
+
+fn write_physics(w: &mut impl Write, physics: &PhysicsConfig) -> io::Result<()> {
+    for value in [
+        physics.english,
+        physics.paddle_transfer,
+        physics.spin_transfer,
+        physics.magnus,
+        physics.spin_decay,
+    ] {
+        write_f32(w, value)?;
+    }
+    Ok(())
+}
+
+fn read_physics(r: &mut impl Read) -> io::Result<PhysicsConfig> {
+    Ok(PhysicsConfig {
+        english: read_f32(r)?,
+        paddle_transfer: read_f32(r)?,
+        spin_transfer: read_f32(r)?,
+        magnus: read_f32(r)?,
+        spin_decay: read_f32(r)?,
+    })
+}
+
+fn write_power_ups(w: &mut impl Write, power_ups: &PowerUps) -> io::Result<()> {
+    for value in [power_ups.spawn_every, power_ups.lifetime, power_ups.size] {
+        write_f32(w, value)?;
+    }
+    w.write_all(&power_ups.max_on_court.to_le_bytes())?;
+
+    w.write_all(&(power_ups.kinds.len() as u32).to_le_bytes())?;
+    for spec in &power_ups.kinds {
+        let kind = PowerUpKind::ALL
+            .iter()
+            .position(|&kind| kind == spec.kind)
+            .unwrap_or_default();
+        let (stacking, max) = match spec.stacking {
+            Stacking::Refresh => (0, 0),
+            Stacking::Extend => (1, 0),
+            Stacking::Stack { max } => (2, max),
+        };
+        w.write_all(&[kind as u8, stacking])?;
+        w.write_all(&max.to_le_bytes())?;
+        for value in [spec.weight, spec.duration, spec.amount] {
+            write_f32(w, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_power_ups(r: &mut impl Read) -> io::Result<PowerUps> {
+    let spawn_every = read_f32(r)?;
+    let lifetime = read_f32(r)?;
+    let size = read_f32(r)?;
+    let max_on_court = read_u32(r)?;
+
+    let kinds = (0..read_u32(r)?)
+        .map(|_| {
+            let kind = read_u8(r)?;
+            let kind = *PowerUpKind::ALL
+                .get(kind as usize)
+                .ok_or_else(|| invalid_data(format!("unknown power-up {kind}")))?;
+            let stacking = match (read_u8(r)?, read_u32(r)?) {
+                (0, _) => Stacking::Refresh,
+                (1, _) => Stacking::Extend,
+                (2, max) => Stacking::Stack { max },
+                (other, _) => return Err(invalid_data(format!("unknown stacking {other}"))),
+            };
+            Ok(PowerUpSpec {
+                kind,
+                weight: read_f32(r)?,
+                duration: read_f32(r)?,
+                amount: read_f32(r)?,
+                stacking,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+
+    Ok(PowerUps {
+        spawn_every,
+        max_on_court,
+        lifetime,
+        size,
+        kinds,
+    })
+}
+
+fn write_rules(w: &mut impl Write, rules: &MatchRules) -> io::Result<()> {
+    write_str(w, &rules.name)?;
+    for value in [rules.target_score, rules.win_by, rules.sets] {
+        w.write_all(&value.to_le_bytes())?;
+    }
+
+    let (serve, every) = match rules.serve {
+        ServeRule::ToLoser => (0, 0),
+        ServeRule::Alternate { every } => (1, every),
+        ServeRule::ToWinner => (2, 0),
+    };
+    w.write_all(&[serve])?;
+    w.write_all(&every.to_le_bytes())?;
+
+    let serve_from = match rules.serve_from {
+        ServeFrom::Center => 0,
+        ServeFrom::Server => 1,
+    };
+    w.write_all(&[serve_from, rules.manual_serve as u8])?;
+    write_f32(w, rules.serve_countdown)?;
+    write_f32(w, rules.serve_angle)?;
+
+    write_speed(w, &rules.speed)?;
+    write_motion(w, &rules.paddle_motion)?;
+
+    let formation = match rules.formation {
+        Formation::Singles => 0,
+        Formation::Depth => 1,
+        Formation::Split => 2,
+    };
+    w.write_all(&[formation])?;
+
+    let multiball = &rules.multiball;
+    for value in [multiball.balls, multiball.after_hits] {
+        w.write_all(&value.to_le_bytes())?;
+    }
+    write_f32(w, multiball.spread)?;
+    w.write_all(&[rules.power_ups as u8])?;
+
+    w.write_all(&[rules.time_limit.is_some() as u8])?;
+    write_f32(w, rules.time_limit.unwrap_or_default())
+}
+
+fn write_speed(w: &mut impl Write, speed: &SpeedModel) -> io::Result<()> {
+    let (curve, every, increase) = match speed.curve {
+        SpeedCurve::Linear { increase } => (0, 0, increase),
+        SpeedCurve::Steps { every, increase } => (1, every, increase),
+        SpeedCurve::Exponential { factor } => (2, 0, factor),
+    };
+    w.write_all(&[curve])?;
+    w.write_all(&every.to_le_bytes())?;
+    for value in [increase, speed.base, speed.max, speed.max_angle] {
+        write_f32(w, value)?;
+    }
+    Ok(())
+}
+
+fn read_speed(r: &mut impl Read) -> io::Result<SpeedModel> {
+    let curve = match (read_u8(r)?, read_u32(r)?, read_f32(r)?) {
+        (0, _, increase) => SpeedCurve::Linear { increase },
+        (1, every, increase) => SpeedCurve::Steps { every, increase },
+        (2, _, factor) => SpeedCurve::Exponential { factor },
+        (other, _, _) => return Err(invalid_data(format!("unknown speed curve {other}"))),
+    };
+
+    Ok(SpeedModel {
+        base: read_f32(r)?,
+        curve,
+        max: read_f32(r)?,
+        max_angle: read_f32(r)?,
+    })
+}
+
+fn read_rules(r: &mut impl Read) -> io::Result<MatchRules> {
+    let name = read_str(r)?;
+    let target_score = read_u32(r)?;
+    let win_by = read_u32(r)?;
+    let sets = read_u32(r)?;
+
+    let serve = match (read_u8(r)?, read_u32(r)?) {
+        (0, _) => ServeRule::ToLoser,
+        (1, every) => ServeRule::Alternate { every },
+        (2, _) => ServeRule::ToWinner,
+        (other, _) => return Err(invalid_data(format!("unknown serve rule {other}"))),
+    };
+
+    let serve_from = match read_u8(r)? {
+        0 => ServeFrom::Center,
+        1 => ServeFrom::Server,
+        other => return Err(invalid_data(format!("unknown serve position {other}"))),
+    };
+    let manual_serve = read_u8(r)? != 0;
+
+    Ok(MatchRules {
+        name,
+        target_score,
+        win_by,
+        sets,
+        serve,
+        serve_from,
+        manual_serve,
+        serve_countdown: read_f32(r)?,
+        serve_angle: read_f32(r)?,
+        speed: read_speed(r)?,
+        paddle_motion: read_motion(r)?,
+        formation: match read_u8(r)? {
+            0 => Formation::Singles,
+            1 => Formation::Depth,
+            2 => Formation::Split,
+            other => return Err(invalid_data(format!("unknown formation {other}"))),
+        },
+        multiball: Multiball {
+            balls: read_u32(r)?,
+            after_hits: read_u32(r)?,
+            spread: read_f32(r)?,
+        },
+        power_ups: read_u8(r)? != 0,
+        time_limit: match (read_u8(r)?, read_f32(r)?) {
+            (0, _) => None,
+            (_, limit) => Some(limit),
+        },
+    })
+}
+
+fn write_motion(w: &mut impl Write, motion: &PaddleMotion) -> io::Result<()> {
+    for value in [
+        motion.acceleration,
+        motion.friction,
+        motion.dash_speed,
+        motion.dash_time,
+        motion.dash_cooldown,
+    ] {
+        write_f32(w, value)?;
+    }
+    Ok(())
+}
+
+fn read_motion(r: &mut impl Read) -> io::Result<PaddleMotion> {
+    Ok(PaddleMotion {
+        acceleration: read_f32(r)?,
+        friction: read_f32(r)?,
+        dash_speed: read_f32(r)?,
+        dash_time: read_f32(r)?,
+        dash_cooldown: read_f32(r)?,
+    })
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn write_f32(w: &mut impl Write, value: f32) -> io::Result<()> {
+    w.write_all(&value.to_le_bytes())
+}
+
+fn write_str(w: &mut impl Write, value: &str) -> io::Result<()> {
+    let len = u16::try_from(value.len()).map_err(|_| invalid_data("string too long"))?;
+    w.write_all(&len.to_le_bytes())?;
+    w.write_all(value.as_bytes())
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f32(r: &mut impl Read) -> io::Result<f32> {
+    Ok(f32::from_bits(read_u32(r)?))
+}
+
+fn read_str(r: &mut impl Read) -> io::Result<String> {
+    let mut buf = vec![0; read_u16(r)? as usize];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(|_| invalid_data("invalid utf-8"))
+}
+
+/// The replay being written for the current match.
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+    /// Ticks already written to [`LATEST_REPLAY`].
+    saved_ticks: usize,
+}
+
+impl ReplayRecorder {
+    /// Writes the recording out if it has ticks that aren't saved yet.
+    fn save(&mut self) {
+        let ticks = self.replay.inputs.len();
+        if ticks > self.saved_ticks {
+            save_latest(&self.replay);
+            self.saved_ticks = ticks;
+        }
+    }
+}
+
+/// The replay being watched, and the live match it temporarily replaces.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    /// Next tick to play.
+    pub cursor: usize,
+    live: Option<(Simulation, FixedTime, Arena)>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            cursor: 0,
+            live: None,
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.cursor >= self.replay.inputs.len()
+    }
+}
+
+/// Moves a paddle the way it was moved in a recorded match.
+#[derive(Component)]
+pub struct Playback {
+    /// The paddle's input on every recorded tick.
+    inputs: Vec<PaddleInput>,
+}
+
+impl Playback {
+    pub fn new(replay: &Replay, paddle: PaddleId) -> Self {
+        Self {
+            inputs: replay
+                .inputs
+                .iter()
+                .map(|tick| tick[paddle.index()])
+                .collect(),
+        }
+    }
+}
+
+impl PaddleController for Playback {
+    /// The input recorded for the tick the simulation is on, so playback
+    /// can't drift from the match however it is paused or stepped.
+    fn next_input(&mut self, ctx: &ControlContext) -> PaddleInput {
+        let tick = ctx.sim.tick as usize;
+        self.inputs.get(tick).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Component)]
+struct PlaybackLabel;
+
+/// Starts a fresh recording along with every new match, keeping the one it
+/// replaces when that match was restarted before it ended.
+fn start_recording(
+    mut commands: Commands,
+    previous: Option<ResMut<ReplayRecorder>>,
+    simulation: Res<Simulation>,
+    arena: Res<Arena>,
+    fixed_time: Res<FixedTime>,
+) {
+    if let Some(mut previous) = previous {
+        previous.save();
+    }
+
+    let tick_rate = 1.0 / fixed_time.period.as_secs_f32();
+
+    commands.insert_resource(ReplayRecorder {
+        replay: Replay::new(ReplayHeader::new(&simulation.sim, &arena, tick_rate)),
+        saved_ticks: 0,
+    });
+}
+
+fn record_inputs(
+    mut recorder: ResMut<ReplayRecorder>,
+    simulation: Res<Simulation>,
+    inputs: Res<PaddleInputs>,
+) {
+    recorder.replay.record(&simulation.sim, inputs.0);
+}
+
+/// Keeps a match quit to the menu before it ended; finished ones are saved
+/// as they end, and pausing alone saves nothing.
+fn save_abandoned_match(recorder: Option<ResMut<ReplayRecorder>>) {
+    if let Some(mut recorder) = recorder {
+        recorder.save();
+    }
+}
+
+fn save_finished_match(
+    mut sim_events: EventReader<SimulationEvent>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let finished = sim_events
+        .iter()
+        .any(|event| matches!(event.0, SimEvent::MatchWon { .. }));
+
+    if finished {
+        recorder.save();
+    }
+}
+
+fn save_latest(replay: &Replay) {
+    match replay.save(LATEST_REPLAY) {
+        Ok(()) => info!("Replay saved to {LATEST_REPLAY}"),
+        Err(err) => error!("Failed to save replay: {err}"),
+    }
+}
+
+fn load_playback(commands: &mut Commands, path: &Path) -> bool {
+    match Replay::load(path) {
+        Ok(replay) => {
+            if replay.header.game_version != env!("CARGO_PKG_VERSION") {
+                warn!(
+                    "Replay was recorded with version {}, it may not play back exactly",
+                    replay.header.game_version
+                );
+            }
+            commands.insert_resource(ReplayPlayback::new(replay));
+            true
+        }
+        Err(err) => {
+            error!("Failed to load replay {}: {err}", path.display());
+            false
+        }
+    }
+}
+
+fn play_from_command_line(
+    mut commands: Commands,
+    args: Res<CliArgs>,
+    mut app_state_next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(path) = &args.replay else {
+        return;
+    };
+
+    if load_playback(&mut commands, path) {
+        app_state_next_state.set(AppState::Replay);
+    }
+}
+
+/// Loads the last recorded match for watching in [`AppState::Replay`].
+/// Returns `false`, having logged why, if there is none to load.
+pub fn watch_latest_replay(commands: &mut Commands) -> bool {
+    load_playback(commands, Path::new(LATEST_REPLAY))
+}
+
+fn begin_playback(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    mut simulation: ResMut<Simulation>,
+    mut fixed_time: ResMut<FixedTime>,
+    mut arena: ResMut<Arena>,
+    mut inputs: ResMut<PaddleInputs>,
+) {
+    let header = &playback.replay.header;
+    let replayed = Simulation::new(header.initial_sim());
+    let replay_time = FixedTime::new_from_secs(1.0 / header.tick_rate);
+    let replay_arena = header.arena.clone();
+
+    playback.cursor = 0;
+    playback.live = Some((
+        std::mem::replace(&mut *simulation, replayed),
+        std::mem::replace(&mut *fixed_time, replay_time),
+        std::mem::replace(&mut *arena, replay_arena),
+    ));
+    *inputs = PaddleInputs::default();
+
+    commands.spawn((
+        Name::new("lblReplay"),
+        PlaybackLabel,
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+    ));
+}
+
+/// The replay is watched on a court of its own, sized to the recorded arena,
+/// with every paddle following its recorded inputs.
+fn spawn_replay_court(
+    mut court: CourtSpawner,
+    simulation: Res<Simulation>,
+    playback: Res<ReplayPlayback>,
+) {
+    let paddles = court.spawn(StateScoped(&[AppState::Replay]), &simulation.sim);
+
+    for (id, paddle) in simulation.sim.paddle_ids().zip(paddles) {
+        let controller = Playback::new(&playback.replay, id);
+        court
+            .commands()
+            .entity(paddle)
+            .add(SetController(controller));
+    }
+}
+
+fn end_playback(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    mut simulation: ResMut<Simulation>,
+    mut fixed_time: ResMut<FixedTime>,
+    mut arena: ResMut<Arena>,
+    mut time: ResMut<Time>,
+    labels: Query<Entity, With<PlaybackLabel>>,
+) {
+    if let Some((live, live_time, live_arena)) = playback.live.take() {
+        *simulation = live;
+        *fixed_time = live_time;
+        *arena = live_arena;
+    }
+
+    time.unpause();
+    time.set_relative_speed(1.0);
+
+    for entity in &labels {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn has_inputs_left(playback: Res<ReplayPlayback>) -> bool {
+    !playback.finished()
+}
+
+/// Retunes the paddles as they were on the tick about to be played, and
+/// counts it off; the [`Playback`] controllers supply the inputs.
+fn advance_playback(mut playback: ResMut<ReplayPlayback>, mut simulation: ResMut<Simulation>) {
+    let tick = simulation.sim.tick as usize;
+    playback.replay.apply_tuning(tick, &mut simulation.sim);
+    playback.cursor += 1;
+}
+
+/// Pausing, stepping a single tick while paused, fast-forwarding and slow
+/// motion, each on its own action.
+fn playback_controls(
+    actions: Res<ActionState>,
+    mut time: ResMut<Time>,
+    mut fixed_time: ResMut<FixedTime>,
+) {
+    if actions.just_pressed(Action::PlaybackPause) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
+    }
+
+    if actions.just_pressed(Action::PlaybackStep) && time.is_paused() {
+        let period = fixed_time.period;
+        fixed_time.tick(period);
+    }
+
+    let toggle = |speed: f32, current: f32| if current == speed { 1.0 } else { speed };
+    if actions.just_pressed(Action::FastForward) {
+        let speed = toggle(4.0, time.relative_speed());
+        time.set_relative_speed(speed);
+    }
+    if actions.just_pressed(Action::SlowMotion) {
+        let speed = toggle(0.25, time.relative_speed());
+        time.set_relative_speed(speed);
+    }
+}
+
+fn update_playback_label(
+    playback: Res<ReplayPlayback>,
+    time: Res<Time>,
+    mut labels: Query<&mut Text, With<PlaybackLabel>>,
+) {
+    let status = if playback.finished() {
+        "END".to_string()
+    } else if time.is_paused() {
+        "PAUSED".to_string()
+    } else {
+        format!("{}x", time.relative_speed())
+    };
+
+    for mut text in &mut labels {
+        text.sections[0].value = format!(
+            "REPLAY {status}  {}/{}",
+            playback.cursor,
+            playback.replay.inputs.len()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A timed doubles match with power-ups, played with random inputs and
+    /// one paddle retuned halfway through.
+    fn recorded_match() -> (Replay, PongSim) {
+        let arena = Arena::default();
+        let rules = MatchRules {
+            formation: Formation::Depth,
+            power_ups: true,
+            time_limit: Some(40.0),
+            ..MatchRules::tennis()
+        };
+        let config = SimConfig::new(&arena).with_power_ups(PowerUps::default());
+        let mut sim = PongSim::new(config, rules).with_rng(Rng::new(7));
+        let mut replay = Replay::new(ReplayHeader::new(&sim, &arena, 120.0));
+
+        let mut rng = Rng::new(11);
+        let mut inputs = [PaddleInput::IDLE; MAX_PADDLES];
+        for tick in 0..6_000 {
+            // hold each input for a while, as people do
+            if tick % 20 == 0 {
+                for input in &mut inputs[..sim.paddles.len()] {
+                    *input = PaddleInput::new(rng.range(-1.0, 1.0))
+                        .serving(rng.next_f32() < 0.3)
+                        .dashing(rng.next_f32() < 0.1);
+                }
+            }
+            if tick == 3_000 {
+                sim.paddles[1].speed = 180.0;
+                sim.paddles[1].motion.acceleration = 900.0;
+            }
+
+            replay.record(&sim, inputs);
+            sim.step(&inputs, 1.0 / replay.header.tick_rate);
+        }
+
+        (replay, sim)
+    }
+
+    fn play_back(replay: &Replay) -> PongSim {
+        let mut sim = replay.header.initial_sim();
+        for (tick, inputs) in replay.inputs.iter().enumerate() {
+            replay.apply_tuning(tick, &mut sim);
+            sim.step(inputs, 1.0 / replay.header.tick_rate);
+        }
+        sim
+    }
+
+    #[test]
+    fn a_replay_reads_back_as_written() {
+        let (replay, _) = recorded_match();
+        assert_eq!(replay.tuning.len(), 1);
+
+        let mut bytes = Vec::new();
+        replay.write(&mut bytes).unwrap();
+        // runs of held input take far less than a byte per paddle and tick
+        assert!(bytes.len() < replay.inputs.len());
+
+        let read = Replay::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, replay);
+    }
+
+    #[test]
+    fn long_names_survive_a_round_trip() {
+        let (mut replay, _) = recorded_match();
+        replay.header.rules.name = "Marathon doubles with power-ups ".repeat(20);
+        replay.header.arena.name = "Très grand court ".repeat(20);
+
+        let mut bytes = Vec::new();
+        replay.write(&mut bytes).unwrap();
+        let read = Replay::read(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read.header, replay.header);
+    }
+
+    #[test]
+    fn playing_a_replay_reproduces_the_match() {
+        let (replay, live) = recorded_match();
+        assert!(live.stats.points > 0, "nothing happened: {:?}", live.stats);
+
+        let first = play_back(&replay);
+        let second = play_back(&replay);
+        for sim in [&first, &second] {
+            assert_eq!(sim.scores, live.scores);
+            assert_eq!(sim.sets, live.sets);
+            assert_eq!(sim.balls, live.balls);
+            assert_eq!(sim.paddles, live.paddles);
+            assert_eq!(sim.tick, live.tick);
+        }
+    }
+}
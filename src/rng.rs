@@ -0,0 +1,107 @@
+//! The one source of randomness for gameplay.
+//!
+//! Everything random that affects play draws from [`GameRng`], or from an
+//! [`Rng`] forked off it, so a run started with the same `--seed` plays out
+//! the same way. Each match gets its own generator, whose seed replays store.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Small xorshift generator that remembers what it was seeded with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            state: scramble(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Uniform in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    /// A new generator seeded from this one, for a consumer that should not
+    /// disturb the draws of others.
+    pub fn fork(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+}
+
+/// SplitMix64, so that nearby seeds still start far apart and a seed of zero
+/// does not leave xorshift stuck at zero.
+fn scramble(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    match z ^ (z >> 31) {
+        0 => 1,
+        state => state,
+    }
+}
+
+/// Seed of the run and the generator every match and computer player is
+/// forked from.
+#[derive(Resource, Clone, Debug, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct GameRng {
+    /// Editing it in the inspector reseeds the next match.
+    pub seed: u64,
+    #[reflect(ignore)]
+    rng: Rng,
+}
+
+impl Default for GameRng {
+    /// Seeded from the clock; pass `--seed` for a repeatable run.
+    fn default() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        Self::new(nanos)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+
+    pub fn fork(&mut self) -> Rng {
+        if self.rng.seed() != self.seed {
+            self.rng = Rng::new(self.seed);
+        }
+        self.rng.fork()
+    }
+}
@@ -0,0 +1,954 @@
+//! How a match is scored and won, and how fast it is played.
+//!
+//! [`MatchRules`] is plain data the simulation consults on every serve, paddle
+//! hit and goal. Rules come from one of the built-in [`MatchRules::presets`]
+//! or a RON file, picked with `--rules <preset or file>`.
+
+use std::{cmp::Ordering, fs, io, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::sim::Side;
+
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatchRules {
+    pub name: String,
+    /// Points needed to win a set.
+    pub target_score: u32,
+    /// Lead over the opponent needed to win a set; 2 plays out deuces.
+    pub win_by: u32,
+    /// Match length, best of this many sets.
+    pub sets: u32,
+    /// Seconds of rally play a match lasts at most. When they run out the
+    /// side ahead wins, and a tied match goes to whoever scores next.
+    pub time_limit: Option<f32>,
+    pub serve: ServeRule,
+    /// Where the ball waits to be served.
+    pub serve_from: ServeFrom,
+    /// Seconds counted down before each serve, while the paddles recenter.
+    pub serve_countdown: f32,
+    /// Widest launch angle of a serve either side of straight, in degrees.
+    pub serve_angle: f32,
+    /// The server launches the ball with the serve button, aiming with the
+    /// paddle controls, instead of it going off at a random angle on its own.
+    pub manual_serve: bool,
+    /// Ball speed over a rally, replacing any speed-up from the hit itself.
+    pub speed: SpeedModel,
+    /// How the paddles pick up and lose speed.
+    pub paddle_motion: PaddleMotion,
+    /// How many paddles each side fields and where they stand.
+    pub formation: Formation,
+    /// Whether, and when, the ball splits into several during a rally.
+    pub multiball: Multiball,
+    /// Power-ups drop onto the court during rallies, as listed in
+    /// [`PowerUps`](crate::powerup::PowerUps).
+    pub power_ups: bool,
+}
+
+/// Which side the ball is served towards after a point; the other side
+/// serves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServeRule {
+    /// Towards the side that just conceded.
+    ToLoser,
+    /// Towards the side that just scored, so the loser serves.
+    ToWinner,
+    /// Swaps sides every `every` points of a set, starting towards the left.
+    Alternate { every: u32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServeFrom {
+    /// The middle of the court.
+    Center,
+    /// In front of the server's paddle, following it until served.
+    Server,
+}
+
+/// How fast the ball travels as a rally goes on, and how steep it may fly.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeedModel {
+    /// Speed of every serve.
+    pub base: f32,
+    /// How the speed grows with the paddle hits of a rally.
+    pub curve: SpeedCurve,
+    /// Speed the ball never goes beyond, whatever the curve says.
+    pub max: f32,
+    /// Widest angle from horizontal, in degrees, the ball is ever allowed,
+    /// coming off a paddle or curving with spin.
+    pub max_angle: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpeedCurve {
+    /// `increase` faster with every hit.
+    Linear { increase: f32 },
+    /// `increase` faster after every `every` hits.
+    Steps { every: u32, increase: f32 },
+    /// `factor` times as fast with every hit.
+    Exponential { factor: f32 },
+}
+
+impl Default for SpeedModel {
+    fn default() -> Self {
+        Self {
+            base: 50.0,
+            curve: SpeedCurve::Exponential { factor: 1.1 },
+            max: 140.0,
+            max_angle: 60.0,
+        }
+    }
+}
+
+impl SpeedModel {
+    /// Ball speed after `hits` paddle hits in a rally.
+    pub fn speed(&self, hits: u32) -> f32 {
+        let hits_f = hits as f32;
+        let speed = match self.curve {
+            SpeedCurve::Linear { increase } => self.base + increase * hits_f,
+            SpeedCurve::Steps { every, increase } => {
+                self.base + increase * (hits / every.max(1)) as f32
+            }
+            SpeedCurve::Exponential { factor } => self.base * factor.powf(hits_f),
+        };
+        speed.clamp(0.0, self.max)
+    }
+
+    /// [`SpeedModel::max_angle`] in radians, kept short of vertical.
+    pub fn max_angle_radians(&self) -> f32 {
+        self.max_angle.clamp(0.0, 85.0).to_radians()
+    }
+
+    /// `velocity` turned to within the allowed angle of horizontal and
+    /// scaled to `speed`, keeping its horizontal direction.
+    pub fn shape(&self, velocity: Vec2, speed: f32) -> Vec2 {
+        let max_angle = self.max_angle_radians();
+        let angle = velocity
+            .y
+            .atan2(velocity.x.abs())
+            .clamp(-max_angle, max_angle);
+        let direction = if velocity.x < 0.0 { -1.0 } else { 1.0 };
+        Vec2::new(direction * angle.cos(), angle.sin()) * speed
+    }
+}
+
+/// How many paddles defend each side, and how they share the work.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Formation {
+    /// One paddle a side.
+    #[default]
+    Singles,
+    /// Two a side, one in front of the other; the back paddle covers what
+    /// gets past the front one.
+    Depth,
+    /// Two a side on the same line, one guarding the top half of the goal
+    /// and one the bottom half.
+    Split,
+}
+
+impl Formation {
+    pub const ALL: [Formation; 3] = [Formation::Singles, Formation::Depth, Formation::Split];
+
+    pub fn paddles_per_side(self) -> usize {
+        match self {
+            Formation::Singles => 1,
+            Formation::Depth | Formation::Split => 2,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Formation::Singles => "Off",
+            Formation::Depth => "Front & Back",
+            Formation::Split => "Top & Bottom",
+        }
+    }
+
+    /// The formation after this one, wrapping around.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+/// Splitting the ball into several partway through a rally. Every ball
+/// scores on its own, and the next serve waits until all of them are out.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Multiball {
+    /// Balls in play after a split, the original included; fewer than two
+    /// never splits.
+    pub balls: u32,
+    /// Paddle hits into a rally on which the ball splits.
+    pub after_hits: u32,
+    /// Degrees between neighbouring balls coming out of a split.
+    pub spread: f32,
+}
+
+impl Default for Multiball {
+    fn default() -> Self {
+        Self::OFF
+    }
+}
+
+impl Multiball {
+    pub const OFF: Multiball = Multiball {
+        balls: 1,
+        after_hits: 4,
+        spread: 20.0,
+    };
+
+    /// Most balls the menu offers to split into.
+    pub const MAX_BALLS: u32 = 3;
+
+    /// Whether the hit that makes a rally `hits` long splits the ball.
+    pub fn splits_on(&self, hits: u32) -> bool {
+        self.balls >= 2 && hits == self.after_hits.max(1)
+    }
+
+    pub fn name(&self) -> String {
+        match self.balls {
+            0 | 1 => "Off".into(),
+            balls => format!("{balls} balls"),
+        }
+    }
+
+    /// One more ball per split, wrapping back around to off.
+    pub fn next(self) -> Self {
+        let balls = if self.balls >= Self::MAX_BALLS {
+            1
+        } else {
+            self.balls.max(1) + 1
+        };
+        Self { balls, ..self }
+    }
+}
+
+/// How a paddle gets up to speed, slows down again and dashes.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaddleMotion {
+    /// Units per second squared a held direction speeds the paddle up by.
+    /// Zero moves it at full speed the instant a direction is held and stops
+    /// it the instant it is let go.
+    pub acceleration: f32,
+    /// Units per second squared the paddle slows down by once let go.
+    pub friction: f32,
+    /// Speed of a dash, in units per second; zero disables dashing.
+    pub dash_speed: f32,
+    /// Seconds a dash lasts.
+    pub dash_time: f32,
+    /// Seconds from the start of a dash until the next one.
+    pub dash_cooldown: f32,
+}
+
+impl Default for PaddleMotion {
+    fn default() -> Self {
+        Self::INSTANT
+    }
+}
+
+impl PaddleMotion {
+    /// The paddle goes exactly where it is pushed, like the original arcade
+    /// game.
+    pub const INSTANT: PaddleMotion = PaddleMotion {
+        acceleration: 0.0,
+        friction: 0.0,
+        dash_speed: 0.0,
+        dash_time: 0.0,
+        dash_cooldown: 0.0,
+    };
+
+    /// Paddles with some weight to them, and a dash to make up for it.
+    pub fn momentum() -> Self {
+        Self {
+            acceleration: 600.0,
+            friction: 900.0,
+            dash_speed: 260.0,
+            dash_time: 0.12,
+            dash_cooldown: 1.0,
+        }
+    }
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+impl MatchRules {
+    pub fn classic() -> Self {
+        Self {
+            name: "Classic 11".into(),
+            target_score: 11,
+            win_by: 1,
+            sets: 1,
+            time_limit: None,
+            serve: ServeRule::ToLoser,
+            serve_from: ServeFrom::Center,
+            serve_countdown: 3.0,
+            serve_angle: 30.0,
+            manual_serve: false,
+            speed: SpeedModel::default(),
+            paddle_motion: PaddleMotion::INSTANT,
+            formation: Formation::Singles,
+            multiball: Multiball::OFF,
+            power_ups: false,
+        }
+    }
+
+    pub fn quick() -> Self {
+        Self {
+            name: "Quick 5".into(),
+            target_score: 5,
+            ..Self::classic()
+        }
+    }
+
+    pub fn tennis() -> Self {
+        Self {
+            name: "Tennis-style deuce".into(),
+            target_score: 4,
+            win_by: 2,
+            sets: 3,
+            serve: ServeRule::Alternate { every: 2 },
+            serve_from: ServeFrom::Server,
+            manual_serve: true,
+            paddle_motion: PaddleMotion::momentum(),
+            ..Self::classic()
+        }
+    }
+
+    pub fn timed() -> Self {
+        Self {
+            name: "Timed 3 min".into(),
+            time_limit: Some(180.0),
+            ..Self::classic()
+        }
+    }
+
+    pub fn presets() -> [MatchRules; 4] {
+        [
+            Self::classic(),
+            Self::quick(),
+            Self::tennis(),
+            Self::timed(),
+        ]
+    }
+
+    /// A preset by (case-insensitive) name or first word, otherwise a RON file
+    /// at `arg`.
+    pub fn from_arg(arg: &str) -> io::Result<Self> {
+        let preset = Self::presets().into_iter().find(|rules| {
+            let name = rules.name.to_lowercase();
+            let arg = arg.to_lowercase();
+            name == arg || name.split(|c: char| !c.is_alphanumeric()).next() == Some(&arg)
+        });
+
+        match preset {
+            Some(rules) => Ok(rules),
+            None => Self::load(arg),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        ron::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// The preset after this one, wrapping around and played in the same
+    /// formation and with the same multiball and power-ups; custom rules go
+    /// back to the first preset.
+    pub fn next_preset(&self) -> Self {
+        let presets = Self::presets().map(|rules| Self {
+            formation: self.formation,
+            multiball: self.multiball,
+            power_ups: self.power_ups,
+            ..rules
+        });
+        let next = presets
+            .iter()
+            .position(|rules| rules == self)
+            .map_or(0, |index| (index + 1) % presets.len());
+        presets[next].clone()
+    }
+
+    pub fn sets_to_win(&self) -> u32 {
+        self.sets.max(1) / 2 + 1
+    }
+
+    /// Who has won the set with `scores`, if anyone.
+    pub fn set_winner(&self, scores: [u32; 2]) -> Option<Side> {
+        Side::ALL.into_iter().find(|side| {
+            let score = scores[side.index()];
+            let other = scores[side.opponent().index()];
+            score >= self.target_score && score >= other + self.win_by.max(1)
+        })
+    }
+
+    /// Who has won the match on time after `clock` seconds of play, with
+    /// `sets` and `scores` standing, if time is up and anyone is ahead.
+    pub fn time_winner(&self, clock: f32, sets: [u32; 2], scores: [u32; 2]) -> Option<Side> {
+        if clock < self.time_limit? {
+            return None;
+        }
+
+        let standing = |side: Side| (sets[side.index()], scores[side.index()]);
+        match standing(Side::Left).cmp(&standing(Side::Right)) {
+            Ordering::Greater => Some(Side::Left),
+            Ordering::Less => Some(Side::Right),
+            Ordering::Equal => None,
+        }
+    }
+
+    /// Side the next serve goes to, after `scorer` made the score `scores`.
+    pub fn receiver(&self, scorer: Side, scores: [u32; 2]) -> Side {
+        match self.serve {
+            ServeRule::ToLoser => scorer.opponent(),
+            ServeRule::ToWinner => scorer,
+            ServeRule::Alternate { every } => {
+                let played = scores[0] + scores[1];
+                match (played / every.max(1)) % 2 {
+                    0 => Side::Left,
+                    _ => Side::Right,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rng::Rng,
+        sim::{
+            tests::in_play, BallId, BallState, PaddleId, PaddleInput, PongSim, SimConfig, SimEvent,
+            MAX_PADDLES,
+        },
+    };
+
+    /// Puts the ball in play just short of the goal `scorer` attacks and
+    /// lets it roll in.
+    fn score_point(sim: &mut PongSim, scorer: Side) -> Vec<SimEvent> {
+        sim.serve = None;
+        sim.balls[0].position = Vec2::new(scorer.forward() * (sim.config.goal_x - 1.0), 0.0);
+        sim.balls[0].velocity = Vec2::new(scorer.forward() * 100.0, 0.0);
+        sim.step(&[], 1.0 / 60.0)
+    }
+
+    #[test]
+    fn deuce_goes_on_until_a_two_point_lead() {
+        let rules = MatchRules::tennis();
+
+        assert_eq!(rules.set_winner([3, 0]), None);
+        assert_eq!(rules.set_winner([4, 2]), Some(Side::Left));
+        assert_eq!(rules.set_winner([3, 3]), None);
+        assert_eq!(rules.set_winner([4, 3]), None);
+        assert_eq!(rules.set_winner([10, 10]), None);
+        assert_eq!(rules.set_winner([11, 10]), None);
+        assert_eq!(rules.set_winner([12, 10]), Some(Side::Left));
+        assert_eq!(rules.set_winner([10, 12]), Some(Side::Right));
+
+        let classic = MatchRules::classic();
+        assert_eq!(classic.set_winner([11, 10]), Some(Side::Left));
+    }
+
+    #[test]
+    fn the_match_goes_to_whoever_wins_most_of_the_sets() {
+        assert_eq!(MatchRules::classic().sets_to_win(), 1);
+        assert_eq!(MatchRules::tennis().sets_to_win(), 2);
+        let best_of_five = MatchRules {
+            sets: 5,
+            ..MatchRules::quick()
+        };
+        assert_eq!(best_of_five.sets_to_win(), 3);
+
+        let mut sim = PongSim::new(SimConfig::default(), best_of_five);
+        let target = sim.rules.target_score;
+        for (set, winner) in [Side::Left, Side::Right, Side::Left, Side::Right, Side::Left]
+            .into_iter()
+            .enumerate()
+        {
+            for point in 1..=target {
+                let events = score_point(&mut sim, winner);
+                let set_over = events.contains(&SimEvent::SetWon { winner });
+                assert_eq!(set_over, point == target, "set {set}, point {point}");
+            }
+            assert_eq!(sim.winner.is_some(), set == 4, "after set {set}");
+        }
+
+        assert_eq!(sim.sets, [3, 2]);
+        assert_eq!(sim.scores, [target, 0]);
+        assert_eq!(sim.winner, Some(Side::Left));
+    }
+
+    #[test]
+    fn alternating_serves_swap_every_few_points() {
+        let rules = MatchRules::tennis();
+        let receivers: Vec<_> = (0..8)
+            .map(|played| rules.receiver(Side::Left, [played, 0]))
+            .collect();
+        assert_eq!(
+            receivers,
+            [
+                Side::Left,
+                Side::Left,
+                Side::Right,
+                Side::Right,
+                Side::Left,
+                Side::Left,
+                Side::Right,
+                Side::Right,
+            ]
+        );
+
+        let classic = MatchRules::classic();
+        assert_eq!(classic.receiver(Side::Left, [1, 0]), Side::Right);
+        let to_winner = MatchRules {
+            serve: ServeRule::ToWinner,
+            ..MatchRules::classic()
+        };
+        assert_eq!(to_winner.receiver(Side::Left, [1, 0]), Side::Left);
+    }
+
+    #[test]
+    fn the_side_ahead_when_time_runs_out_wins() {
+        let rules = MatchRules::timed();
+
+        assert_eq!(rules.time_winner(179.0, [0, 0], [5, 3]), None);
+        assert_eq!(rules.time_winner(180.0, [0, 0], [5, 3]), Some(Side::Left));
+        assert_eq!(rules.time_winner(200.0, [0, 0], [4, 4]), None);
+        // sets count before points
+        assert_eq!(rules.time_winner(200.0, [0, 1], [6, 0]), Some(Side::Right));
+        assert_eq!(MatchRules::classic().time_winner(1e6, [0, 0], [5, 3]), None);
+
+        let mut sim = PongSim::new(SimConfig::default(), rules);
+        score_point(&mut sim, Side::Right);
+        score_point(&mut sim, Side::Left);
+
+        // level at the buzzer, so the next point decides it
+        sim.serve = None;
+        sim.clock = 180.0;
+        assert!(sim.step(&[], 1.0 / 60.0).is_empty());
+        assert_eq!(sim.winner, None);
+
+        let events = score_point(&mut sim, Side::Right);
+        assert!(events.contains(&SimEvent::MatchWon {
+            winner: Side::Right
+        }));
+        assert_eq!(sim.winner, Some(Side::Right));
+        assert_eq!(sim.scores, [1, 2]);
+    }
+
+    #[test]
+    fn reaching_the_target_score_wins_the_match() {
+        let mut sim = in_play();
+        sim.scores = [0, sim.rules.target_score - 1];
+        sim.balls[0].position = Vec2::new(-sim.config.goal_x + 1.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(-100.0, 0.0);
+
+        let events = sim.step(&[PaddleInput::IDLE; 2], 1.0 / 60.0);
+        assert_eq!(
+            events,
+            [
+                SimEvent::Goal {
+                    scorer: Side::Right,
+                    ball: BallId(0),
+                },
+                SimEvent::SetWon {
+                    winner: Side::Right
+                },
+                SimEvent::MatchWon {
+                    winner: Side::Right
+                },
+            ]
+        );
+        assert_eq!(sim.winner, Some(Side::Right));
+        assert_eq!(sim.scores, [0, sim.rules.target_score]);
+
+        // a decided match stays as it ended
+        let tick = sim.tick;
+        assert!(sim.step(&[PaddleInput::new(1.0); 2], 1.0 / 60.0).is_empty());
+        assert_eq!(sim.tick, tick);
+        assert_eq!(sim.scores, [0, sim.rules.target_score]);
+    }
+
+    #[test]
+    fn a_point_short_of_the_target_does_not_win() {
+        let mut sim = in_play();
+        let target = sim.rules.target_score;
+        sim.scores = [target - 2, target - 2];
+        sim.balls[0].position = Vec2::new(sim.config.goal_x - 1.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(100.0, 0.0);
+
+        let events = sim.step(&[PaddleInput::IDLE; 2], 1.0 / 60.0);
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, SimEvent::SetWon { .. } | SimEvent::MatchWon { .. })));
+        assert_eq!(sim.scores, [target - 1, target - 2]);
+        assert_eq!(sim.winner, None);
+        assert!(sim.serve.is_some());
+    }
+
+    #[test]
+    fn serves_after_the_countdown_within_the_angle_limit() {
+        let mut sim = PongSim::default();
+        let dt = 1.0 / 120.0;
+        let ticks = (sim.rules.serve_countdown / dt).ceil() as usize;
+
+        for _ in 1..ticks {
+            assert!(sim.step(&[PaddleInput::new(1.0); 2], dt).is_empty());
+            assert_eq!(sim.balls[0].position, Vec2::ZERO);
+        }
+        assert_eq!(sim.paddle(Side::Left).position.y, 0.0);
+
+        let events = sim.step(&[PaddleInput::IDLE; 2], dt);
+        let Some(SimEvent::Served { server, velocity }) = events.first().copied() else {
+            panic!("expected a serve, got {events:?}");
+        };
+
+        assert_eq!(server, Side::Right);
+        assert!(velocity.x < 0.0);
+        let angle = velocity.y.atan2(-velocity.x).to_degrees();
+        assert!(angle.abs() <= sim.rules.serve_angle + 1e-3);
+        assert!((velocity.length() - sim.rules.speed.base).abs() < 1e-3);
+    }
+
+    #[test]
+    fn manual_serve_waits_for_the_server_and_follows_its_aim() {
+        let mut sim = PongSim::new(SimConfig::default(), MatchRules::tennis());
+        sim.serve.as_mut().unwrap().countdown = 0.0;
+        let dt = 1.0 / 120.0;
+
+        for _ in 0..100 {
+            assert!(sim.step(&[PaddleInput::IDLE; 2], dt).is_empty());
+        }
+        let face = sim.paddle(Side::Right).position.x - sim.config.paddle_size.x / 2.0;
+        assert!(sim.balls[0].position.x + sim.config.ball_size.x / 2.0 < face);
+
+        let aimed_up = PaddleInput::new(1.0).serving(true);
+        let events = sim.step(&[PaddleInput::IDLE, aimed_up], dt);
+        assert!(matches!(
+            events.first(),
+            Some(SimEvent::Served {
+                server: Side::Right,
+                ..
+            })
+        ));
+        let angle = sim.balls[0]
+            .velocity
+            .y
+            .atan2(-sim.balls[0].velocity.x)
+            .to_degrees();
+        assert!((angle - sim.rules.serve_angle).abs() < 1e-3);
+    }
+
+    #[test]
+    fn speed_curves_grow_with_the_rally_up_to_the_cap() {
+        let model = |curve| SpeedModel {
+            base: 50.0,
+            curve,
+            max: 100.0,
+            max_angle: 60.0,
+        };
+
+        let linear = model(SpeedCurve::Linear { increase: 5.0 });
+        assert_eq!(linear.speed(0), 50.0);
+        assert_eq!(linear.speed(4), 70.0);
+        assert_eq!(linear.speed(50), 100.0);
+
+        let steps = model(SpeedCurve::Steps {
+            every: 3,
+            increase: 10.0,
+        });
+        assert_eq!(steps.speed(2), 50.0);
+        assert_eq!(steps.speed(3), 60.0);
+        assert_eq!(steps.speed(7), 70.0);
+
+        let exponential = model(SpeedCurve::Exponential { factor: 2.0 });
+        assert_eq!(exponential.speed(1), 100.0);
+        assert_eq!(exponential.speed(2), 100.0);
+    }
+
+    #[test]
+    fn ball_speed_and_angle_stay_within_the_speed_model() {
+        let mut rng = Rng::new(0xd1b5_4a32_d192_ed03);
+
+        for round in 0..60 {
+            let curve = match round % 3 {
+                0 => SpeedCurve::Linear {
+                    increase: rng.range(0.0, 20.0),
+                },
+                1 => SpeedCurve::Steps {
+                    every: 1 + (rng.next_f32() * 5.0) as u32,
+                    increase: rng.range(0.0, 40.0),
+                },
+                _ => SpeedCurve::Exponential {
+                    factor: rng.range(1.0, 1.5),
+                },
+            };
+            let speed = SpeedModel {
+                base: rng.range(30.0, 80.0),
+                curve,
+                max: rng.range(80.0, 300.0),
+                max_angle: rng.range(15.0, 75.0),
+            };
+            let rules = MatchRules {
+                serve_countdown: 0.1,
+                speed: speed.clone(),
+                ..MatchRules::default()
+            };
+            let mut sim =
+                PongSim::new(SimConfig::default(), rules).with_rng(Rng::new(rng.next_u64()));
+
+            let max_angle = speed.max_angle.to_radians();
+            let dt = 1.0 / rng.range(30.0, 240.0);
+            let mut rally = 0;
+            let mut hits = 0;
+
+            for _ in 0..4_000 {
+                // chase the ball sloppily so it meets every part of the paddles
+                let inputs = Side::ALL.map(|side| {
+                    let gap = sim.balls[0].position.y - sim.paddle(side).position.y;
+                    PaddleInput::new(gap / 8.0 + rng.range(-1.0, 1.0))
+                });
+                for event in sim.step(&inputs, dt) {
+                    match event {
+                        SimEvent::Served { .. } => rally = 0,
+                        SimEvent::PaddleHit { velocity, .. } => {
+                            rally += 1;
+                            hits += 1;
+                            assert!(
+                                (velocity.length() - speed.speed(rally)).abs() < 1e-3,
+                                "hit {rally} off the curve: {velocity:?} with {speed:?}"
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+
+                if sim.serve.is_some() {
+                    continue;
+                }
+                let velocity = sim.balls[0].velocity;
+                assert!(
+                    velocity.length() <= speed.max + 1e-3,
+                    "too fast: {velocity:?}"
+                );
+                let angle = velocity.y.atan2(velocity.x.abs()).abs();
+                assert!(angle <= max_angle + 1e-4, "too steep: {velocity:?}");
+            }
+
+            assert!(hits > 0, "no paddle hits with {speed:?}");
+        }
+    }
+
+    #[test]
+    fn split_paddles_guard_their_own_half() {
+        let rules = MatchRules {
+            formation: Formation::Split,
+            ..MatchRules::default()
+        };
+        let mut sim = PongSim::new(SimConfig::default(), rules);
+        sim.serve = None;
+        let limit = sim.config.paddle_limit;
+        let half = sim.config.paddle_size.y / 2.0;
+        let top = PaddleId::new(Side::Left, 0);
+        let bottom = PaddleId::new(Side::Left, 1);
+        assert_eq!(sim.paddles.len(), 4);
+
+        for axis in [1.0, -1.0] {
+            for _ in 0..200 {
+                sim.step(&[PaddleInput::new(axis); MAX_PADDLES], 1.0 / 60.0);
+            }
+            let (top_y, bottom_y) = (sim.paddle(top).position.y, sim.paddle(bottom).position.y);
+            if axis > 0.0 {
+                assert_eq!((top_y, bottom_y), (limit, -half));
+            } else {
+                assert_eq!((top_y, bottom_y), (half, -limit));
+            }
+        }
+    }
+
+    #[test]
+    fn returns_from_the_back_pass_through_the_front_paddle() {
+        let rules = MatchRules {
+            formation: Formation::Depth,
+            ..MatchRules::default()
+        };
+        let mut sim = PongSim::new(SimConfig::default(), rules);
+        sim.serve = None;
+        let front = PaddleId::new(Side::Left, 0);
+        let back = PaddleId::new(Side::Left, 1);
+        assert!(sim.paddle(front).position.x > sim.paddle(back).position.x);
+
+        // on its way in, the front paddle gets the ball first
+        sim.balls[0].position = Vec2::new(0.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(-200.0, 0.0);
+        let mut hit = None;
+        while hit.is_none() {
+            hit = sim
+                .step(&[], 1.0 / 120.0)
+                .into_iter()
+                .find_map(|e| match e {
+                    SimEvent::PaddleHit { contact, .. } => Some(contact),
+                    _ => None,
+                });
+        }
+        let face = sim.paddle(front).position.x + sim.config.paddle_size.x / 2.0;
+        assert!((hit.unwrap().x - face).abs() < 1e-3);
+
+        // on its way out from behind, it goes straight through
+        sim.balls[0].position = Vec2::new(sim.paddle(back).position.x + 10.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(200.0, 0.0);
+        for _ in 0..60 {
+            let events = sim.step(&[], 1.0 / 120.0);
+            assert!(!events
+                .iter()
+                .any(|e| matches!(e, SimEvent::PaddleHit { .. })));
+        }
+        assert!(sim.balls[0].position.x > sim.paddle(front).position.x);
+    }
+
+    #[test]
+    fn multiball_splits_the_ball_on_the_set_hit() {
+        let rules = MatchRules {
+            multiball: Multiball {
+                balls: 3,
+                after_hits: 1,
+                spread: 20.0,
+            },
+            ..MatchRules::default()
+        };
+        let mut sim = PongSim::new(SimConfig::default(), rules);
+        sim.serve = None;
+        sim.balls[0].position = Vec2::new(-80.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(-100.0, 0.0);
+
+        let events = loop {
+            let events = sim.step(&[], 1.0 / 120.0);
+            if events
+                .iter()
+                .any(|e| matches!(e, SimEvent::PaddleHit { .. }))
+            {
+                break events;
+            }
+        };
+        assert!(events.iter().any(|e| matches!(
+            e,
+            SimEvent::BallSplit {
+                ball: BallId(0),
+                ..
+            }
+        )));
+
+        let ids: Vec<_> = sim.balls.iter().map(|ball| ball.id).collect();
+        assert_eq!(ids, [BallId(0), BallId(1), BallId(2)]);
+        let speed = sim.balls[0].velocity.length();
+        for ball in &sim.balls {
+            assert!(ball.velocity.x > 0.0);
+            assert!((ball.velocity.length() - speed).abs() < 1e-3);
+            assert_eq!(ball.last_hit, Some(PaddleId::from(Side::Left)));
+        }
+        assert!(sim.balls[0].velocity.y < 0.0 && sim.balls[2].velocity.y > 0.0);
+    }
+
+    #[test]
+    fn every_ball_scores_and_the_serve_waits_for_the_last() {
+        let mut sim = in_play();
+        let goal = sim.config.goal_x;
+        sim.balls[0].position = Vec2::new(-goal + 1.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(-100.0, 0.0);
+        sim.balls.push(BallState {
+            id: BallId(1),
+            position: Vec2::new(0.0, 20.0),
+            velocity: Vec2::new(10.0, 0.0),
+            ..sim.balls[0].clone()
+        });
+
+        let events = sim.step(&[], 1.0 / 60.0);
+        assert!(events.contains(&SimEvent::Goal {
+            scorer: Side::Right,
+            ball: BallId(0),
+        }));
+        assert_eq!(sim.scores, [0, 1]);
+        assert!(sim.serve.is_none());
+        assert_eq!(sim.balls.len(), 1);
+        assert!(sim.ball(BallId(1)).is_some());
+
+        sim.balls[0].position = Vec2::new(goal - 1.0, 20.0);
+        let events = sim.step(&[], 1.0 / 60.0);
+        assert!(events.contains(&SimEvent::Goal {
+            scorer: Side::Left,
+            ball: BallId(1),
+        }));
+        assert_eq!(sim.scores, [1, 1]);
+        assert!(sim.serve.is_some());
+        assert_eq!(sim.balls.len(), 1);
+    }
+
+    #[test]
+    fn a_ball_wedged_over_a_paddle_slides_through() {
+        let rules = MatchRules {
+            formation: Formation::Depth,
+            ..MatchRules::default()
+        };
+        let mut sim = PongSim::new(SimConfig::default(), rules);
+        sim.serve = None;
+        let front = PaddleId::new(Side::Left, 0);
+        let (wall, ball, paddle) = (
+            sim.config.wall_y,
+            sim.config.ball_size,
+            sim.config.paddle_size,
+        );
+        let x = sim.paddle(front).position.x;
+
+        // just enough room for the ball between the paddle and the top wall
+        sim.paddle_mut(front).position.y = wall - ball.y - paddle.y / 2.0;
+        sim.balls[0].position = Vec2::new(x + 1.0, wall - ball.y / 2.0);
+        sim.balls[0].velocity = Vec2::new(-150.0, 80.0);
+
+        for _ in 0..60 {
+            sim.step(&[], 1.0 / 120.0);
+        }
+        assert!(sim.balls[0].position.x < x - (ball.x + paddle.x) / 2.0);
+    }
+
+    #[test]
+    fn momentum_paddles_speed_up_coast_and_dash() {
+        let mut sim = in_play();
+        let motion = PaddleMotion::momentum();
+        let paddle = sim.paddle_mut(Side::Left);
+        paddle.motion = motion;
+        paddle.speed = 100.0;
+
+        let dt = 1.0 / 100.0;
+        let up = [PaddleInput::new(1.0), PaddleInput::IDLE];
+        sim.step(&up, dt);
+        assert!((sim.paddle(Side::Left).velocity - motion.acceleration * dt).abs() < 1e-3);
+        for _ in 0..30 {
+            sim.step(&up, dt);
+        }
+        assert!((sim.paddle(Side::Left).velocity - 100.0).abs() < 1e-3);
+
+        sim.step(&[PaddleInput::IDLE; 2], dt);
+        let coasting = sim.paddle(Side::Left).velocity;
+        assert!((coasting - (100.0 - motion.friction * dt)).abs() < 1e-3);
+
+        let dash = [PaddleInput::new(-1.0).dashing(true), PaddleInput::IDLE];
+        sim.step(&dash, dt);
+        assert!((sim.paddle(Side::Left).velocity + motion.dash_speed).abs() < 1e-3);
+        let dash_ticks = (motion.dash_time / dt).ceil() as usize;
+        // over, and still cooling down however hard the button is held
+        for _ in 0..dash_ticks + 1 {
+            sim.step(&dash, dt);
+        }
+        assert!(sim.paddle(Side::Left).dash.is_none());
+        assert!(sim.paddle(Side::Left).velocity.abs() < motion.dash_speed);
+        assert!(sim.paddle(Side::Left).dash_cooldown > 0.0);
+    }
+}
@@ -0,0 +1,34 @@
+use std::io;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load_or_default());
+    }
+}
+
+/// Gameplay preferences, kept in [`Settings::PATH`].
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Let the computer ease off or tighten up to keep matches close.
+    pub adaptive_ai: bool,
+}
+
+impl Settings {
+    pub const PATH: &'static str = "config/settings.ron";
+
+    pub fn load_or_default() -> Self {
+        config::load_or_default(Self::PATH)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        config::save(Self::PATH, self)
+    }
+}
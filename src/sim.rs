@@ -0,0 +1,1457 @@
+//! Bevy-independent Pong rules.
+//!
+//! [`PongSim`] owns the balls, the paddles and the score and advances them one
+//! tick at a time with [`PongSim::step`]. The ECS systems only feed it inputs
+//! and mirror its state back onto entities, so the same rules can run headless
+//! in tests, tools, replays or over the network. Only bevy's math types are used
+//! here, nothing from the ECS.
+
+use bevy::math::Vec2;
+
+use crate::{
+    arena::Arena,
+    collision::{self, Hit},
+    powerup::{add_effect, Effect, PowerUpKind, PowerUps},
+    rng::Rng,
+    rules::{Formation, MatchRules, PaddleMotion, ServeFrom},
+};
+
+/// Most surfaces the ball may bounce off within a single tick.
+const MAX_BOUNCES: usize = 4;
+
+/// Most balls in play at once, however often they split.
+pub const MAX_BALLS: usize = 8;
+
+/// One half of the court, identified by the goal it defends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Side {
+    #[default]
+    Left,
+    Right,
+}
+
+impl Side {
+    pub const ALL: [Side; 2] = [Side::Left, Side::Right];
+
+    pub fn index(self) -> usize {
+        match self {
+            Side::Left => 0,
+            Side::Right => 1,
+        }
+    }
+
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left,
+        }
+    }
+
+    /// Sign of the x axis pointing from this side's goal towards the opponent.
+    pub fn forward(self) -> f32 {
+        match self {
+            Side::Left => 1.0,
+            Side::Right => -1.0,
+        }
+    }
+}
+
+/// Most paddles on the court at once, two a side in doubles.
+pub const MAX_PADDLES: usize = 4;
+
+/// One of the paddles on the court: the side it plays for and, in doubles,
+/// which of the two teammates it is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PaddleId {
+    pub side: Side,
+    /// `0` for a side's only paddle in singles, and for its front or top
+    /// paddle in doubles; `1` for the back or bottom one.
+    pub slot: usize,
+}
+
+impl PaddleId {
+    /// Every paddle there can be, in the order of [`PongSim::paddles`]: the
+    /// first paddle of each side, then their teammates.
+    pub const ALL: [PaddleId; MAX_PADDLES] = [
+        PaddleId::new(Side::Left, 0),
+        PaddleId::new(Side::Right, 0),
+        PaddleId::new(Side::Left, 1),
+        PaddleId::new(Side::Right, 1),
+    ];
+
+    pub const fn new(side: Side, slot: usize) -> Self {
+        Self { side, slot }
+    }
+
+    /// Position in [`PongSim::paddles`], and in anything else kept per
+    /// paddle.
+    pub fn index(self) -> usize {
+        self.slot * 2 + self.side.index()
+    }
+}
+
+/// A side's first paddle, the one that serves.
+impl From<Side> for PaddleId {
+    fn from(side: Side) -> Self {
+        Self::new(side, 0)
+    }
+}
+
+/// Court dimensions, taken from an [`Arena`], and how the ball behaves on it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimConfig {
+    pub ball_size: Vec2,
+    pub paddle_size: Vec2,
+    /// Distance of each paddle from the center line.
+    pub paddle_x: f32,
+    /// Distance of the front paddles from the center line in doubles.
+    pub front_x: f32,
+    /// How far a paddle center may travel up or down.
+    pub paddle_limit: f32,
+    /// Height of the top and bottom walls.
+    pub wall_y: f32,
+    /// Distance of each goal line from the center line.
+    pub goal_x: f32,
+    /// Top speed every paddle starts a match with, in units per second.
+    pub paddle_speed: f32,
+    pub physics: PhysicsConfig,
+    /// What drops onto the court when [`MatchRules::power_ups`] is on.
+    pub power_ups: PowerUps,
+}
+
+/// Tunables for how the ball moves and what it picks up from the paddles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhysicsConfig {
+    /// Vertical speed added when the ball hits the very edge of a paddle.
+    pub english: f32,
+    /// Share of the paddle's vertical velocity passed on to the ball.
+    pub paddle_transfer: f32,
+    /// Spin put on the ball per unit of paddle velocity at contact.
+    pub spin_transfer: f32,
+    /// Radians per second the ball's path turns per unit of spin.
+    pub magnus: f32,
+    /// Rate spin dies down at, per second.
+    pub spin_decay: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            english: 50.0,
+            paddle_transfer: 0.3,
+            spin_transfer: 0.01,
+            magnus: 0.6,
+            spin_decay: 1.5,
+        }
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self::new(&Arena::default())
+    }
+}
+
+impl SimConfig {
+    pub fn new(arena: &Arena) -> Self {
+        Self {
+            ball_size: arena.ball_size,
+            paddle_size: arena.paddle_size,
+            paddle_x: arena.paddle_x(),
+            front_x: arena.front_paddle_x(),
+            paddle_limit: arena.paddle_limit(),
+            wall_y: arena.wall_y(),
+            goal_x: arena.goal_x(),
+            paddle_speed: 100.0,
+            physics: PhysicsConfig::default(),
+            power_ups: PowerUps::default(),
+        }
+    }
+
+    /// The same court with `power_ups` to drop onto it.
+    pub fn with_power_ups(self, power_ups: PowerUps) -> Self {
+        Self { power_ups, ..self }
+    }
+
+    /// How far from the center line the paddle `id` stands in `formation`,
+    /// and the lowest and highest its center may go.
+    pub fn placement(&self, formation: Formation, id: PaddleId) -> (f32, (f32, f32)) {
+        let limit = self.paddle_limit;
+        // split paddles meet in the middle of the goal
+        let half = (self.paddle_size.y / 2.0).min(limit);
+
+        match (formation, id.slot) {
+            (Formation::Depth, 0) => (self.front_x, (-limit, limit)),
+            (Formation::Split, 0) => (self.paddle_x, (half, limit)),
+            (Formation::Split, _) => (self.paddle_x, (-limit, -half)),
+            _ => (self.paddle_x, (-limit, limit)),
+        }
+    }
+}
+
+/// Desired paddle movement for a single tick, `-1.0` (down) to `1.0` (up).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PaddleInput {
+    pub axis: f32,
+    /// Held serve button, for [`MatchRules::manual_serve`].
+    pub serve: bool,
+    /// Held dash button, for a burst in the direction of `axis`.
+    pub dash: bool,
+}
+
+impl PaddleInput {
+    pub const IDLE: PaddleInput = PaddleInput {
+        axis: 0.0,
+        serve: false,
+        dash: false,
+    };
+
+    /// Steps the axis is quantized to, so an input survives a round trip
+    /// through [`PaddleInput::to_i8`] and replays stay exact.
+    const STEPS: f32 = 127.0;
+
+    pub fn new(axis: f32) -> Self {
+        Self::from_i8((axis.clamp(-1.0, 1.0) * Self::STEPS).round() as i8)
+    }
+
+    pub fn from_i8(value: i8) -> Self {
+        Self {
+            axis: (value as f32 / Self::STEPS).clamp(-1.0, 1.0),
+            serve: false,
+            dash: false,
+        }
+    }
+
+    /// The same movement with the serve button held as well.
+    pub fn serving(self, serve: bool) -> Self {
+        Self { serve, ..self }
+    }
+
+    /// The same movement with the dash button held as well.
+    pub fn dashing(self, dash: bool) -> Self {
+        Self { dash, ..self }
+    }
+
+    pub fn to_i8(self) -> i8 {
+        (self.axis * Self::STEPS).round() as i8
+    }
+}
+
+/// Tells the balls of a multiball rally apart. A ball keeps its id for as
+/// long as it stays in play.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BallId(pub u32);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BallState {
+    pub id: BallId,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    /// Turns the ball's path, counterclockwise when positive, and fades out
+    /// over time.
+    pub spin: f32,
+    /// The paddle that hit the ball last, if any has since the serve.
+    pub last_hit: Option<PaddleId>,
+    /// Set while a sticky paddle has caught the ball.
+    pub held: Option<Hold>,
+}
+
+/// A ball caught by a paddle with [`PowerUpKind::Sticky`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hold {
+    pub paddle: PaddleId,
+    /// Height of the ball's center above the paddle's.
+    pub offset: f32,
+    /// Seconds until the ball goes off on its own.
+    pub release_in: f32,
+}
+
+/// Tells the power-ups waiting on court apart.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PickupId(pub u32);
+
+/// A power-up waiting on court for a ball to run through it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pickup {
+    pub id: PickupId,
+    pub kind: PowerUpKind,
+    pub position: Vec2,
+    /// Seconds left before it disappears uncollected.
+    pub remaining: f32,
+}
+
+/// The ball waiting to be put into play.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Serve {
+    pub server: Side,
+    /// Seconds left before the ball can be served.
+    pub countdown: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaddleState {
+    pub position: Vec2,
+    /// Width and height, grown or shrunk by power-ups.
+    pub size: Vec2,
+    /// Units per second at full input.
+    pub speed: f32,
+    /// Factor on `speed` from power-ups.
+    pub boost: f32,
+    /// Vertical movement over the last tick, in units per second.
+    pub velocity: f32,
+    /// Lowest and highest the paddle's center may go.
+    pub zone: (f32, f32),
+    pub motion: PaddleMotion,
+    pub dash: Option<Dash>,
+    /// Seconds until the paddle can dash again.
+    pub dash_cooldown: f32,
+}
+
+/// A dash in progress.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dash {
+    /// `1.0` for up, `-1.0` for down.
+    pub direction: f32,
+    /// Seconds left.
+    pub remaining: f32,
+}
+
+impl PaddleState {
+    /// Where the paddle waits for the next shot, the middle of its zone.
+    pub fn home(&self) -> f32 {
+        (self.zone.0 + self.zone.1) / 2.0
+    }
+
+    /// Units per second at full input, boost included.
+    pub fn top_speed(&self) -> f32 {
+        self.speed * self.boost
+    }
+
+    /// Moves the paddle for a tick of `input` under its [`PaddleMotion`],
+    /// keeping its center within its zone and its ends short of the walls
+    /// at `wall_y`.
+    fn steer(&mut self, input: PaddleInput, wall_y: f32, dt: f32) {
+        let motion = self.motion;
+        let axis = input.axis.clamp(-1.0, 1.0);
+
+        self.dash_cooldown = (self.dash_cooldown - dt).max(0.0);
+        let can_dash = motion.dash_speed > 0.0 && self.dash.is_none() && self.dash_cooldown <= 0.0;
+        if input.dash && axis != 0.0 && can_dash {
+            self.dash = Some(Dash {
+                direction: axis.signum(),
+                remaining: motion.dash_time,
+            });
+            self.dash_cooldown = motion.dash_cooldown;
+        }
+
+        let target = axis * self.top_speed();
+        let velocity = if let Some(dash) = &mut self.dash {
+            let velocity = dash.direction * motion.dash_speed;
+            dash.remaining -= dt;
+            if dash.remaining <= 0.0 {
+                self.dash = None;
+            }
+            velocity
+        } else if motion.acceleration <= 0.0 {
+            target
+        } else {
+            let rate = if axis == 0.0 {
+                motion.friction
+            } else {
+                motion.acceleration
+            };
+            let step = rate * dt;
+            self.velocity + (target - self.velocity).clamp(-step, step)
+        };
+
+        let reach = (wall_y - self.size.y / 2.0).max(0.0);
+        let y = (self.position.y + velocity * dt)
+            .clamp(self.zone.0, self.zone.1)
+            .clamp(-reach, reach);
+        self.move_to(y, dt);
+    }
+
+    fn move_to(&mut self, y: f32, dt: f32) {
+        if dt > 0.0 {
+            self.velocity = (y - self.position.y) / dt;
+        }
+        self.position.y = y;
+    }
+}
+
+/// Something noteworthy that happened during a tick.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimEvent {
+    /// `ball` bounced off a paddle at `contact`, leaving with `velocity`.
+    PaddleHit {
+        ball: BallId,
+        side: Side,
+        contact: Vec2,
+        normal: Vec2,
+        velocity: Vec2,
+        spin: f32,
+    },
+    WallBounce {
+        contact: Vec2,
+        normal: Vec2,
+    },
+    Served {
+        server: Side,
+        velocity: Vec2,
+    },
+    /// `ball` went into the goal of `scorer`'s opponent.
+    Goal {
+        scorer: Side,
+        ball: BallId,
+    },
+    /// `ball` split into several at `position`; the new balls are the last
+    /// ones in [`PongSim::balls`].
+    BallSplit {
+        ball: BallId,
+        position: Vec2,
+    },
+    /// A ball last hit by `side` ran through a power-up at `position`.
+    PowerUpCollected {
+        kind: PowerUpKind,
+        side: Side,
+        position: Vec2,
+    },
+    SetWon {
+        winner: Side,
+    },
+    MatchWon {
+        winner: Side,
+    },
+}
+
+/// Running totals over a match, for the results screen.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchStats {
+    pub serves: u32,
+    pub points: u32,
+    pub paddle_hits: u32,
+    /// Paddle hits since the last serve.
+    pub rally: u32,
+    /// Paddle hits in the point before this one.
+    pub last_rally: u32,
+    pub longest_rally: u32,
+    pub top_speed: f32,
+}
+
+/// What the ball bounced off.
+#[derive(Clone, Copy, Debug)]
+enum Obstacle {
+    Wall,
+    Paddle(PaddleId),
+}
+
+#[derive(Clone, Debug)]
+pub struct PongSim {
+    pub config: SimConfig,
+    pub rules: MatchRules,
+    /// Every ball in play, never empty; the first one is the one served.
+    pub balls: Vec<BallState>,
+    /// Every paddle in play, indexed by [`PaddleId::index`].
+    pub paddles: Vec<PaddleState>,
+    /// Points in the current set.
+    pub scores: [u32; 2],
+    /// Sets won in the current match.
+    pub sets: [u32; 2],
+    pub stats: MatchStats,
+    /// Set between points, while the ball is held for the next serve.
+    pub serve: Option<Serve>,
+    /// Drives serve angles, so a match plays out the same from the same
+    /// seed and inputs.
+    pub rng: Rng,
+    /// Set once the match is decided; the simulation stops advancing and the
+    /// final scores stay on the board.
+    pub winner: Option<Side>,
+    /// Number of ticks simulated so far.
+    pub tick: u64,
+    /// Seconds of rally play so far, what [`MatchRules::time_limit`] is
+    /// measured against.
+    pub clock: f32,
+    /// Power-ups waiting on court to be collected.
+    pub pickups: Vec<Pickup>,
+    /// Power-up effects running on either side.
+    pub effects: Vec<Effect>,
+    /// Id for the next ball split off.
+    next_ball: u32,
+    /// Seconds of play until the next power-up drops.
+    next_drop: f32,
+    /// Id for the next power-up dropped.
+    next_pickup: u32,
+}
+
+impl Default for PongSim {
+    fn default() -> Self {
+        Self::new(SimConfig::default(), MatchRules::default())
+    }
+}
+
+impl PongSim {
+    pub fn new(config: SimConfig, rules: MatchRules) -> Self {
+        let formation = rules.formation;
+        let paddles = PaddleId::ALL
+            .into_iter()
+            .take(2 * formation.paddles_per_side())
+            .map(|id| {
+                let (x, zone) = config.placement(formation, id);
+                PaddleState {
+                    position: Vec2::new(-id.side.forward() * x, (zone.0 + zone.1) / 2.0),
+                    size: config.paddle_size,
+                    speed: config.paddle_speed,
+                    boost: 1.0,
+                    velocity: 0.0,
+                    zone,
+                    motion: rules.paddle_motion,
+                    dash: None,
+                    dash_cooldown: 0.0,
+                }
+            })
+            .collect();
+
+        let mut sim = Self {
+            balls: vec![BallState {
+                id: BallId(0),
+                position: Vec2::ZERO,
+                velocity: Vec2::ZERO,
+                spin: 0.0,
+                last_hit: None,
+                held: None,
+            }],
+            paddles,
+            scores: [0, 0],
+            sets: [0, 0],
+            stats: MatchStats::default(),
+            serve: None,
+            rng: Rng::default(),
+            winner: None,
+            tick: 0,
+            clock: 0.0,
+            pickups: Vec::new(),
+            effects: Vec::new(),
+            next_ball: 1,
+            next_drop: config.power_ups.spawn_every,
+            next_pickup: 0,
+            config,
+            rules,
+        };
+        // the opening serve goes to the left
+        sim.prepare_serve(Side::Right);
+        sim
+    }
+
+    /// The same match with its randomness drawn from `rng`.
+    pub fn with_rng(self, rng: Rng) -> Self {
+        Self { rng, ..self }
+    }
+
+    /// The paddle `id`, or a side's first paddle when given a [`Side`].
+    pub fn paddle(&self, id: impl Into<PaddleId>) -> &PaddleState {
+        &self.paddles[id.into().index()]
+    }
+
+    pub fn paddle_mut(&mut self, id: impl Into<PaddleId>) -> &mut PaddleState {
+        &mut self.paddles[id.into().index()]
+    }
+
+    /// The paddles in play, in the order of [`PongSim::paddles`].
+    pub fn paddle_ids(&self) -> impl Iterator<Item = PaddleId> {
+        PaddleId::ALL.into_iter().take(self.paddles.len())
+    }
+
+    /// The ball `id`, or `None` once it has left play.
+    pub fn ball(&self, id: BallId) -> Option<&BallState> {
+        self.balls.iter().find(|ball| ball.id == id)
+    }
+
+    /// The `kind` of effect running on `side`, the one started first if
+    /// several are.
+    pub fn effect(&self, side: Side, kind: PowerUpKind) -> Option<&Effect> {
+        self.effects
+            .iter()
+            .find(|effect| effect.side == side && effect.kind == kind)
+    }
+
+    pub fn has_effect(&self, side: Side, kind: PowerUpKind) -> bool {
+        self.effect(side, kind).is_some()
+    }
+
+    /// The amounts of every `kind` of effect running on `side` multiplied
+    /// together, `1.0` with none.
+    fn effect_factor(&self, side: Side, kind: PowerUpKind) -> f32 {
+        self.effects
+            .iter()
+            .filter(|effect| effect.side == side && effect.kind == kind)
+            .map(|effect| effect.amount)
+            .product()
+    }
+
+    pub fn score(&self, side: Side) -> u32 {
+        self.scores[side.index()]
+    }
+
+    pub fn sets_won(&self, side: Side) -> u32 {
+        self.sets[side.index()]
+    }
+
+    /// Sets won when playing more than one, otherwise points.
+    pub fn match_score(&self) -> [u32; 2] {
+        if self.rules.sets > 1 {
+            self.sets
+        } else {
+            self.scores
+        }
+    }
+
+    /// Advances the simulation by `dt` seconds and returns what happened.
+    /// `inputs` are indexed by [`PaddleId::index`], and paddles without one
+    /// stay idle. Does nothing once the match has a winner.
+    pub fn step(&mut self, inputs: &[PaddleInput], dt: f32) -> Vec<SimEvent> {
+        let mut events = Vec::new();
+        if self.winner.is_some() {
+            return events;
+        }
+
+        if self.serve.is_some() {
+            self.wait_for_serve(inputs, dt, &mut events);
+        } else {
+            self.clock += dt;
+            self.tick_effects(dt);
+            self.move_paddles(inputs, dt);
+            self.carry_held_balls(inputs, dt);
+
+            let ball_dt = dt * self.ball_pace();
+            for ball in 0..self.balls.len() {
+                self.move_ball(ball, ball_dt, &mut events);
+            }
+            self.collect_power_ups(&mut events);
+            self.drop_power_ups(dt);
+            self.check_goals(&mut events);
+        }
+        self.check_time(&mut events);
+
+        self.tick += 1;
+        events
+    }
+
+    /// Ends the match once [`MatchRules::time_limit`] is up, unless the
+    /// scores are level.
+    fn check_time(&mut self, events: &mut Vec<SimEvent>) {
+        if self.winner.is_some() {
+            return;
+        }
+
+        if let Some(winner) = self.rules.time_winner(self.clock, self.sets, self.scores) {
+            events.push(SimEvent::MatchWon { winner });
+            self.winner = Some(winner);
+        }
+    }
+
+    /// Counts down while the paddles glide back to the middle, then puts the
+    /// ball into play, either straight away or when the server asks for it.
+    fn wait_for_serve(&mut self, inputs: &[PaddleInput], dt: f32, events: &mut Vec<SimEvent>) {
+        let Some(serve) = &mut self.serve else {
+            return;
+        };
+        serve.countdown -= dt;
+        let ready = serve.countdown <= 0.0;
+        let server = serve.server;
+
+        if ready {
+            self.move_paddles(inputs, dt);
+        } else {
+            self.recenter_paddles(dt);
+        }
+        self.hold_ball(server);
+
+        let input = input_for(inputs, server.into());
+        if ready && (input.serve || !self.rules.manual_serve) {
+            self.launch(server, input, events);
+        }
+    }
+
+    fn recenter_paddles(&mut self, dt: f32) {
+        for paddle in &mut self.paddles {
+            let step = paddle.speed * dt;
+            let gap = paddle.position.y - paddle.home();
+            let y = paddle.position.y - gap.clamp(-step, step);
+            paddle.move_to(y, dt);
+        }
+    }
+
+    /// Keeps the ball where [`MatchRules::serve_from`] says it waits.
+    fn hold_ball(&mut self, server: Side) {
+        let position = match self.rules.serve_from {
+            ServeFrom::Center => Vec2::ZERO,
+            ServeFrom::Server => {
+                let gap = (self.config.ball_size.x + self.config.paddle_size.x) / 2.0 + 1.0;
+                self.paddle(server).position + Vec2::new(server.forward() * gap, 0.0)
+            }
+        };
+
+        self.balls.truncate(1);
+        let ball = &mut self.balls[0];
+        ball.position = position;
+        ball.velocity = Vec2::ZERO;
+        ball.spin = 0.0;
+        ball.last_hit = None;
+        ball.held = None;
+    }
+
+    /// Sends the ball off towards the receiver, aimed by the server's
+    /// controls on a manual serve and at a random angle otherwise.
+    fn launch(&mut self, server: Side, input: PaddleInput, events: &mut Vec<SimEvent>) {
+        let max_angle = self
+            .rules
+            .serve_angle
+            .to_radians()
+            .clamp(0.0, self.rules.speed.max_angle_radians());
+        let speed = self.rules.speed.speed(0);
+        let angle = if self.rules.manual_serve {
+            input.axis.clamp(-1.0, 1.0) * max_angle
+        } else {
+            self.rng.range(-max_angle, max_angle)
+        };
+
+        let velocity = Vec2::new(server.forward() * angle.cos(), angle.sin()) * speed;
+        self.balls[0].velocity = velocity;
+        self.serve = None;
+        self.stats.serves += 1;
+        events.push(SimEvent::Served { server, velocity });
+    }
+
+    /// Holds the ball for `server` and starts the countdown.
+    fn prepare_serve(&mut self, server: Side) {
+        self.serve = Some(Serve {
+            server,
+            countdown: self.rules.serve_countdown,
+        });
+        self.hold_ball(server);
+    }
+
+    fn move_paddles(&mut self, inputs: &[PaddleInput], dt: f32) {
+        for id in self.paddle_ids() {
+            let mut input = input_for(inputs, id);
+            if self.has_effect(id.side, PowerUpKind::ReverseControls) {
+                input.axis = -input.axis;
+            }
+            let wall_y = self.config.wall_y;
+            self.paddle_mut(id).steer(input, wall_y, dt);
+        }
+    }
+
+    /// Runs down the power-up effects, ending the ones whose time is up, and
+    /// sizes and speeds up the paddles by what is left.
+    fn tick_effects(&mut self, dt: f32) {
+        for effect in &mut self.effects {
+            effect.remaining -= dt;
+        }
+        self.effects.retain(|effect| effect.remaining > 0.0);
+
+        let base = self.config.paddle_size;
+        for id in self.paddle_ids() {
+            let height = self.effect_factor(id.side, PowerUpKind::Grow)
+                * self.effect_factor(id.side, PowerUpKind::Shrink);
+            let boost = self.effect_factor(id.side, PowerUpKind::SpeedBoost);
+
+            let paddle = self.paddle_mut(id);
+            paddle.size = Vec2::new(base.x, base.y * height);
+            paddle.boost = boost;
+        }
+    }
+
+    /// Share of a tick the balls travel through, less than one while
+    /// slowed by power-ups.
+    fn ball_pace(&self) -> f32 {
+        Side::ALL
+            .iter()
+            .map(|&side| self.effect_factor(side, PowerUpKind::SlowBall))
+            .product()
+    }
+
+    /// Keeps each caught ball on the face of the paddle holding it, and
+    /// sends it off once that paddle serves it or it has been held long
+    /// enough, aimed like a manual serve and as fast as it came in.
+    fn carry_held_balls(&mut self, inputs: &[PaddleInput], dt: f32) {
+        let max_angle = self.rules.speed.max_angle_radians();
+        let limit = self.ball_limit();
+
+        for index in 0..self.balls.len() {
+            let Some(mut hold) = self.balls[index].held else {
+                continue;
+            };
+            let paddle = self.paddle(hold.paddle);
+            let forward = hold.paddle.side.forward();
+            let gap = (self.config.ball_size.x + paddle.size.x) / 2.0;
+            let reach = paddle.size.y / 2.0;
+            let offset = Vec2::new(forward * gap, hold.offset.clamp(-reach, reach));
+            let position = paddle.position + offset;
+
+            hold.release_in -= dt;
+            let input = input_for(inputs, hold.paddle);
+            let ball = &mut self.balls[index];
+            ball.position = Vec2::new(position.x, position.y.clamp(-limit, limit));
+
+            if input.serve || hold.release_in <= 0.0 {
+                let angle = input.axis.clamp(-1.0, 1.0) * max_angle;
+                let speed = ball.velocity.length();
+                ball.velocity = Vec2::new(forward * angle.cos(), angle.sin()) * speed;
+                ball.held = None;
+            } else {
+                ball.held = Some(hold);
+            }
+        }
+    }
+
+    /// Bends the path of ball `index` by its spin, which then fades a little.
+    fn curve_ball(&mut self, index: usize, dt: f32) {
+        let physics = &self.config.physics;
+        let ball = &mut self.balls[index];
+        if ball.spin == 0.0 {
+            return;
+        }
+
+        let curved = Vec2::from_angle(physics.magnus * ball.spin * dt).rotate(ball.velocity);
+        let max_slope = self.rules.speed.max_angle_radians().tan();
+        let too_steep =
+            curved.x * ball.velocity.x <= 0.0 || curved.y.abs() > curved.x.abs() * max_slope;
+
+        if too_steep {
+            ball.spin = 0.0;
+        } else {
+            ball.velocity = curved;
+            ball.spin *= (-physics.spin_decay * dt).exp();
+        }
+    }
+
+    /// Sweeps ball `index` through the tick, bouncing off everything it
+    /// touches on the way instead of only checking where it ends up.
+    fn move_ball(&mut self, index: usize, dt: f32, events: &mut Vec<SimEvent>) {
+        if self.balls[index].held.is_some() {
+            return;
+        }
+        self.curve_ball(index, dt);
+        let mut remaining = dt;
+
+        for _ in 0..MAX_BOUNCES {
+            self.separate_from_paddles(index, events);
+
+            let ball = &self.balls[index];
+            let motion = ball.velocity * remaining;
+            let Some((hit, obstacle)) = self.first_hit(ball, motion) else {
+                self.balls[index].position += motion;
+                return;
+            };
+
+            self.balls[index].position += motion * hit.time;
+            self.bounce(index, obstacle, hit.normal, events);
+
+            remaining *= 1.0 - hit.time;
+            if remaining <= 0.0 || self.balls[index].held.is_some() {
+                return;
+            }
+        }
+    }
+
+    /// A paddle can move into the ball; push the ball back out of it.
+    fn separate_from_paddles(&mut self, index: usize, events: &mut Vec<SimEvent>) {
+        for id in self.paddle_ids() {
+            let ball = &self.balls[index];
+            if !self.can_hit(ball, id) {
+                continue;
+            }
+            let paddle = self.paddle(id);
+            let half = (self.config.ball_size + paddle.size) / 2.0;
+            let Some(push) = collision::penetration(ball.position, paddle.position, half) else {
+                continue;
+            };
+
+            self.balls[index].position += push;
+
+            let normal = push.normalize();
+            if self.balls[index].velocity.dot(normal) < 0.0 {
+                self.bounce(index, Obstacle::Paddle(id), normal, events);
+            }
+        }
+    }
+
+    /// Whether `ball` can run into paddle `id`. On its way out of a side it
+    /// passes through paddles with a teammate behind them, so a return from
+    /// the back isn't stopped by the side's own front paddle.
+    fn can_hit(&self, ball: &BallState, id: PaddleId) -> bool {
+        let forward = id.side.forward();
+        let x = self.paddle(id).position.x;
+        let leaving = ball.velocity.x * forward > 0.0;
+
+        !leaving
+            || !self.paddle_ids().any(|other| {
+                other.side == id.side && (self.paddle(other).position.x - x) * forward < 0.0
+            })
+    }
+
+    fn first_hit(&self, ball: &BallState, motion: Vec2) -> Option<(Hit, Obstacle)> {
+        let paddles = self
+            .paddle_ids()
+            .filter(|&id| self.can_hit(ball, id))
+            .filter_map(|id| {
+                let paddle = self.paddle(id);
+                let half = (self.config.ball_size + paddle.size) / 2.0;
+                collision::sweep(ball.position, motion, paddle.position, half)
+                    .map(|hit| (hit, Obstacle::Paddle(id)))
+            });
+
+        paddles
+            .chain(self.wall_hit(ball, motion).map(|hit| (hit, Obstacle::Wall)))
+            .min_by(|(a, _), (b, _)| a.time.total_cmp(&b.time))
+    }
+
+    /// How far the ball's center may go up or down before touching a wall.
+    fn ball_limit(&self) -> f32 {
+        self.config.wall_y - self.config.ball_size.y / 2.0
+    }
+
+    fn wall_hit(&self, ball: &BallState, motion: Vec2) -> Option<Hit> {
+        let limit = self.ball_limit();
+        let y = ball.position.y;
+
+        let (time, normal) = if motion.y > 0.0 {
+            ((limit - y) / motion.y, Vec2::NEG_Y)
+        } else if motion.y < 0.0 {
+            ((-limit - y) / motion.y, Vec2::Y)
+        } else {
+            return None;
+        };
+
+        // already past the wall counts as touching it right away
+        (time <= 1.0).then_some(Hit {
+            time: time.max(0.0),
+            normal,
+        })
+    }
+
+    fn bounce(
+        &mut self,
+        index: usize,
+        obstacle: Obstacle,
+        normal: Vec2,
+        events: &mut Vec<SimEvent>,
+    ) {
+        let limit = self.ball_limit();
+        // a sticky paddle catches what hits its face, for this long
+        let catch = match obstacle {
+            Obstacle::Paddle(id) if normal.x != 0.0 => self
+                .effect(id.side, PowerUpKind::Sticky)
+                .map(|effect| effect.amount),
+            _ => None,
+        };
+        let ball = &mut self.balls[index];
+        let contact = ball.position - normal * self.config.ball_size / 2.0;
+
+        match obstacle {
+            Obstacle::Wall => {
+                // a mirrored path curves the mirrored way
+                ball.velocity.y *= -1.0;
+                ball.spin *= -1.0;
+                events.push(SimEvent::WallBounce { contact, normal });
+            }
+            Obstacle::Paddle(id) => {
+                let side = id.side;
+                let paddle = &self.paddles[id.index()];
+                let physics = &self.config.physics;
+                let wedged = normal.y * ball.position.y >= limit - 1e-3;
+
+                if normal.x != 0.0 {
+                    let adjustment = (ball.position.y - paddle.position.y) / (paddle.size.y / 2.0);
+                    ball.velocity.x *= -1.0;
+                    ball.velocity.y += adjustment * physics.english;
+
+                    // a moving paddle drags the ball along and sets it
+                    // curving back against the drag
+                    ball.velocity.y += paddle.velocity * physics.paddle_transfer;
+                    ball.spin = -side.forward() * paddle.velocity * physics.spin_transfer;
+                } else if wedged {
+                    // squeezed between the paddle and a wall, the ball can
+                    // only slide along the gap
+                    ball.velocity.y = 0.0;
+                } else {
+                    // clipped the top or bottom of the paddle
+                    ball.velocity.y *= -1.0;
+                }
+                ball.last_hit = Some(id);
+
+                let stats = &mut self.stats;
+                stats.paddle_hits += 1;
+                stats.rally += 1;
+                stats.longest_rally = stats.longest_rally.max(stats.rally);
+
+                // only the direction comes from the hit; how fast and how
+                // steep the ball may go is up to the rules
+                let speed = &self.rules.speed;
+                ball.velocity = speed.shape(ball.velocity, speed.speed(stats.rally));
+                stats.top_speed = stats.top_speed.max(ball.velocity.length());
+
+                events.push(SimEvent::PaddleHit {
+                    ball: ball.id,
+                    side,
+                    contact,
+                    normal,
+                    velocity: ball.velocity,
+                    spin: ball.spin,
+                });
+
+                if let Some(release_in) = catch {
+                    ball.held = Some(Hold {
+                        paddle: id,
+                        offset: ball.position.y - self.paddles[id.index()].position.y,
+                        release_in,
+                    });
+                }
+
+                let multiball = self.rules.multiball;
+                if self.balls.len() == 1 && multiball.splits_on(self.stats.rally) {
+                    let id = self.balls[index].id;
+                    self.split_ball(id, multiball.balls, multiball.spread, events);
+                }
+            }
+        }
+    }
+
+    /// Splits ball `id` into `count` balls, fanned out `spread` degrees
+    /// apart around its path at the same speed. It keeps its id and the
+    /// others get new ones; no more than [`MAX_BALLS`] are ever in play.
+    fn split_ball(&mut self, id: BallId, count: u32, spread: f32, events: &mut Vec<SimEvent>) {
+        let Some(index) = self.balls.iter().position(|ball| ball.id == id) else {
+            return;
+        };
+        let room = MAX_BALLS.saturating_sub(self.balls.len()) + 1;
+        let count = (count as usize).min(room);
+        if self.serve.is_some() || count < 2 {
+            return;
+        }
+
+        let ball = self.balls[index].clone();
+        let speed = ball.velocity.length();
+        for split in 0..count {
+            let angle = (split as f32 - (count - 1) as f32 / 2.0) * spread.to_radians();
+            let velocity = Vec2::from_angle(angle).rotate(ball.velocity);
+            let velocity = self.rules.speed.shape(velocity, speed);
+
+            if split == 0 {
+                self.balls[index].velocity = velocity;
+            } else {
+                let id = BallId(self.next_ball);
+                self.next_ball += 1;
+                self.balls.push(BallState {
+                    id,
+                    velocity,
+                    held: None,
+                    ..ball.clone()
+                });
+            }
+        }
+
+        events.push(SimEvent::BallSplit {
+            ball: id,
+            position: ball.position,
+        });
+    }
+
+    /// Scores every ball that is past a goal line. A ball that scores
+    /// leaves play, unless it is the last one, which is held for the next
+    /// serve.
+    fn check_goals(&mut self, events: &mut Vec<SimEvent>) {
+        let mut index = 0;
+
+        while index < self.balls.len() {
+            let Some(scorer) = self.scorer(&self.balls[index]) else {
+                index += 1;
+                continue;
+            };
+            if self.has_effect(scorer.opponent(), PowerUpKind::Shield) {
+                self.block_goal(index, scorer.opponent(), events);
+                index += 1;
+                continue;
+            }
+
+            let ball = self.balls[index].id;
+            let last = self.balls.len() == 1;
+            if !last {
+                self.balls.remove(index);
+            }
+
+            self.award_point(scorer, ball, last, events);
+            if self.serve.is_some() || self.winner.is_some() {
+                return;
+            }
+        }
+    }
+
+    /// Bounces ball `index` back off the shield across `side`'s goal.
+    fn block_goal(&mut self, index: usize, side: Side, events: &mut Vec<SimEvent>) {
+        let half_ball = self.config.ball_size / 2.0;
+        let normal = Vec2::new(side.forward(), 0.0);
+        let ball = &mut self.balls[index];
+
+        ball.position.x = -side.forward() * (self.config.goal_x - half_ball.x);
+        ball.velocity.x = side.forward() * ball.velocity.x.abs();
+        ball.spin *= -1.0;
+        events.push(SimEvent::WallBounce {
+            contact: ball.position - normal * half_ball,
+            normal,
+        });
+    }
+
+    /// Hands every power-up a ball has run into to the side that hit that
+    /// ball last. A ball nobody has hit since the serve passes through them.
+    fn collect_power_ups(&mut self, events: &mut Vec<SimEvent>) {
+        let half = (self.config.ball_size + Vec2::splat(self.config.power_ups.size)) / 2.0;
+        let mut index = 0;
+
+        while index < self.pickups.len() {
+            let position = self.pickups[index].position;
+            let collector = self.balls.iter().find_map(|ball| {
+                let id = ball.last_hit?;
+                collision::penetration(ball.position, position, half).map(|_| (ball.id, id.side))
+            });
+            let Some((ball, side)) = collector else {
+                index += 1;
+                continue;
+            };
+
+            let kind = self.pickups.remove(index).kind;
+            events.push(SimEvent::PowerUpCollected {
+                kind,
+                side,
+                position,
+            });
+            self.apply_power_up(kind, side, ball, events);
+        }
+    }
+
+    /// Starts the effect of a `kind` of power-up that `collector` got with
+    /// `ball`, following the power-up table.
+    fn apply_power_up(
+        &mut self,
+        kind: PowerUpKind,
+        collector: Side,
+        ball: BallId,
+        events: &mut Vec<SimEvent>,
+    ) {
+        let Some(spec) = self.config.power_ups.spec(kind) else {
+            return;
+        };
+
+        match kind {
+            PowerUpKind::Multiball => {
+                let count = spec.amount.max(0.0) as u32;
+                let spread = self.rules.multiball.spread;
+                self.split_ball(ball, count, spread, events);
+            }
+            _ => add_effect(&mut self.effects, spec, kind.target(collector)),
+        }
+    }
+
+    /// Clears away power-ups left uncollected too long and, with
+    /// [`MatchRules::power_ups`] on, drops a new one at a random spot between
+    /// the paddles every so often.
+    fn drop_power_ups(&mut self, dt: f32) {
+        for pickup in &mut self.pickups {
+            pickup.remaining -= dt;
+        }
+        self.pickups.retain(|pickup| pickup.remaining > 0.0);
+
+        let table = &self.config.power_ups;
+        if !self.rules.power_ups {
+            return;
+        }
+        self.next_drop -= dt;
+        if self.next_drop > 0.0 {
+            return;
+        }
+        self.next_drop = table.spawn_every;
+        if self.pickups.len() >= table.max_on_court as usize {
+            return;
+        }
+        let Some(spec) = table.pick(self.rng.next_f32()) else {
+            return;
+        };
+
+        let half = table.size / 2.0;
+        let x = (self.config.front_x - self.config.paddle_size.x - half).max(0.0);
+        let y = (self.config.wall_y - half).max(0.0);
+        let position = Vec2::new(self.rng.range(-x, x), self.rng.range(-y, y));
+
+        self.pickups.push(Pickup {
+            id: PickupId(self.next_pickup),
+            kind: spec.kind,
+            position,
+            remaining: table.lifetime,
+        });
+        self.next_pickup += 1;
+    }
+
+    /// The side `ball` scores for, if it is past either goal line.
+    fn scorer(&self, ball: &BallState) -> Option<Side> {
+        let half_ball = self.config.ball_size.x / 2.0;
+        let goal = self.config.goal_x;
+
+        if ball.position.x - half_ball < -goal {
+            Some(Side::Right)
+        } else if ball.position.x + half_ball > goal {
+            Some(Side::Left)
+        } else {
+            None
+        }
+    }
+
+    /// Gives `scorer` the point won with `ball`. The point is over once the
+    /// `last` ball is out, or as soon as it decides the set; until then the
+    /// rally goes on with the other balls.
+    fn award_point(&mut self, scorer: Side, ball: BallId, last: bool, events: &mut Vec<SimEvent>) {
+        self.scores[scorer.index()] += 1;
+        self.stats.points += 1;
+        events.push(SimEvent::Goal { scorer, ball });
+
+        let set_winner = self.rules.set_winner(self.scores);
+        if !last && set_winner.is_none() {
+            return;
+        }
+        self.stats.last_rally = std::mem::take(&mut self.stats.rally);
+
+        if let Some(winner) = set_winner {
+            self.sets[winner.index()] += 1;
+            events.push(SimEvent::SetWon { winner });
+
+            if self.sets_won(winner) >= self.rules.sets_to_win() {
+                events.push(SimEvent::MatchWon { winner });
+                self.winner = Some(winner);
+                return;
+            }
+
+            self.scores = [0, 0];
+        }
+
+        let receiver = self.rules.receiver(scorer, self.scores);
+        self.prepare_serve(receiver.opponent());
+    }
+}
+
+/// The input for paddle `id`, idle if there is none.
+fn input_for(inputs: &[PaddleInput], id: PaddleId) -> PaddleInput {
+    inputs.get(id.index()).copied().unwrap_or_default()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A fresh match with the opening serve skipped.
+    pub(crate) fn in_play() -> PongSim {
+        PongSim {
+            serve: None,
+            ..PongSim::default()
+        }
+    }
+
+    /// Shoots the ball straight at the left paddle's face from anywhere on
+    /// the court, at any speed up to `max_speed`.
+    fn shot_at_left_paddle(rng: &mut Rng, max_speed: f32) -> PongSim {
+        let mut sim = in_play();
+        let config = sim.config.clone();
+        let half = (config.ball_size + config.paddle_size) / 2.0;
+
+        let paddle = Vec2::new(
+            -config.paddle_x,
+            rng.range(-config.paddle_limit, config.paddle_limit),
+        );
+        sim.paddle_mut(Side::Left).position = paddle;
+
+        let target = Vec2::new(
+            paddle.x + half.x,
+            paddle.y + rng.range(-half.y, half.y) * 0.95,
+        );
+        let start = Vec2::new(
+            rng.range(target.x + 1.0, config.paddle_x - half.x),
+            rng.range(-config.wall_y, config.wall_y) * 0.9,
+        );
+
+        sim.balls[0].position = start;
+        sim.balls[0].velocity = (target - start).normalize() * rng.range(20.0, max_speed);
+        sim
+    }
+
+    #[test]
+    fn ball_never_tunnels_through_a_paddle() {
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..5_000 {
+            let mut sim = shot_at_left_paddle(&mut rng, 10_000.0);
+            let dt = 1.0 / rng.range(30.0, 240.0);
+            let face = sim.paddle(Side::Left).position.x + sim.config.paddle_size.x / 2.0;
+
+            let mut hit = false;
+            for _ in 0..10_000 {
+                let events = sim.step(&[PaddleInput::IDLE; 2], dt);
+                hit |= events.iter().any(|e| {
+                    matches!(
+                        e,
+                        SimEvent::PaddleHit {
+                            side: Side::Left,
+                            ..
+                        }
+                    )
+                });
+
+                assert!(
+                    !events.iter().any(|e| matches!(e, SimEvent::Goal { .. })),
+                    "ball went through the paddle: {:?}",
+                    sim.balls[0]
+                );
+                assert!(sim.balls[0].position.x - sim.config.ball_size.x / 2.0 >= face - 1e-3);
+
+                if hit {
+                    break;
+                }
+            }
+
+            assert!(hit, "ball never reached the paddle: {:?}", sim.balls[0]);
+            assert!(sim.balls[0].velocity.x > 0.0);
+        }
+    }
+
+    #[test]
+    fn ball_never_overlaps_a_moving_paddle() {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..200 {
+            let mut sim = in_play();
+            sim.balls[0].velocity = Vec2::new(rng.range(-1.0, 1.0), rng.range(-1.0, 1.0)) * 2_000.0;
+            let dt = 1.0 / rng.range(30.0, 240.0);
+
+            for _ in 0..500 {
+                let inputs = [
+                    PaddleInput::new(rng.range(-1.0, 1.0)),
+                    PaddleInput::new(rng.range(-1.0, 1.0)),
+                ];
+                sim.step(&inputs, dt);
+
+                let half = (sim.config.ball_size + sim.config.paddle_size) / 2.0;
+                for paddle in &sim.paddles {
+                    let overlap =
+                        collision::penetration(sim.balls[0].position, paddle.position, half);
+                    assert!(
+                        !overlap.is_some_and(|push| push.length() >= 1e-3),
+                        "ball inside paddle: {:?} {:?}",
+                        sim.balls[0],
+                        paddle
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reports_contact_on_the_paddle_face() {
+        let mut sim = in_play();
+        sim.balls[0].position = Vec2::new(-50.0, 4.0);
+        sim.balls[0].velocity = Vec2::new(-1_000.0, 0.0);
+
+        let events = sim.step(&[PaddleInput::IDLE; 2], 1.0 / 10.0);
+        let Some(SimEvent::PaddleHit {
+            side,
+            contact,
+            normal,
+            ..
+        }) = events.first().copied()
+        else {
+            panic!("expected a paddle hit, got {events:?}");
+        };
+
+        assert_eq!(side, Side::Left);
+        assert_eq!(normal, Vec2::X);
+        assert!((contact - Vec2::new(-98.0, 4.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn a_ball_past_the_goal_line_scores_for_the_other_side() {
+        let mut sim = in_play();
+        let goal = sim.config.goal_x;
+        sim.balls[0].position = Vec2::new(goal - 1.0, 0.0);
+        sim.balls[0].velocity = Vec2::new(100.0, 0.0);
+
+        let events = sim.step(&[PaddleInput::IDLE; 2], 1.0 / 60.0);
+        assert_eq!(
+            events,
+            [SimEvent::Goal {
+                scorer: Side::Left,
+                ball: BallId(0),
+            }]
+        );
+        assert_eq!(sim.scores, [1, 0]);
+        assert_eq!(sim.stats.points, 1);
+        assert_eq!(sim.winner, None);
+
+        // serves go to the side that lost the point
+        assert_eq!(
+            sim.serve.as_ref().map(|serve| serve.server),
+            Some(Side::Left)
+        );
+        assert!(sim.balls[0].position.x.abs() < goal);
+        assert_eq!(sim.balls[0].velocity, Vec2::ZERO);
+    }
+
+    #[test]
+    fn every_paddle_starts_at_the_configured_speed() {
+        let config = SimConfig {
+            paddle_speed: 70.0,
+            ..SimConfig::default()
+        };
+
+        for formation in Formation::ALL {
+            let rules = MatchRules {
+                formation,
+                ..MatchRules::classic()
+            };
+            let sim = PongSim::new(config.clone(), rules);
+            assert!(sim.paddles.iter().all(|paddle| paddle.speed == 70.0));
+        }
+    }
+
+    #[test]
+    fn a_moving_paddle_drags_and_curves_the_ball() {
+        let return_after_hit = |axis: f32| {
+            let mut sim = in_play();
+            sim.paddle_mut(Side::Left).position.y = -10.0;
+            sim.balls[0].position = Vec2::new(-80.0, 0.0);
+            sim.balls[0].velocity = Vec2::new(-200.0, 0.0);
+
+            let dt = 1.0 / 120.0;
+            loop {
+                let events = sim.step(&[PaddleInput::new(axis), PaddleInput::IDLE], dt);
+                if events
+                    .iter()
+                    .any(|e| matches!(e, SimEvent::PaddleHit { .. }))
+                {
+                    return sim;
+                }
+            }
+        };
+
+        let still = return_after_hit(0.0);
+        let moving = return_after_hit(1.0);
+        assert_eq!(still.balls[0].spin, 0.0);
+        assert!(moving.balls[0].velocity.y > still.balls[0].velocity.y);
+        // going right and curving back down
+        assert!(moving.balls[0].spin < 0.0);
+
+        let mut sim = moving;
+        let launch = sim.balls[0].velocity;
+        let spin = sim.balls[0].spin;
+        sim.step(&[PaddleInput::IDLE; 2], 1.0 / 10.0);
+        assert!(sim.balls[0].velocity.y < launch.y);
+        assert!((sim.balls[0].velocity.length() - launch.length()).abs() < 1e-3);
+        assert!(sim.balls[0].spin.abs() < spin.abs());
+    }
+}
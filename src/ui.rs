@@ -1,20 +1,31 @@
-use bevy::prelude::*;
+use bevy::{ecs::query::Has, prelude::*};
 
-use crate::{ai::AI, game::Score, player::Player, AppState};
+use crate::{
+    ai::AI,
+    game::{MatchScore, Paddle, Simulation, StateScoped},
+    powerup::Effect,
+    sim::Side,
+    AppState,
+};
 
 pub struct GameUiPlugin;
 
 impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup)
-            .add_systems(Update, update_score.run_if(in_state(AppState::Game)));
+        app.add_systems(
+            Update,
+            (update_score, update_serve_countdown, update_effects),
+        );
     }
 }
 
-fn setup(mut commands: Commands) {
+/// Score display with the match clock, serve countdown and each side's
+/// running power-ups, living as long as `scope`.
+pub fn spawn_hud(commands: &mut Commands, scope: StateScoped) {
     commands
         .spawn((
             Name::new("UI Root"),
+            scope,
             NodeBundle {
                 style: Style {
                     display: Display::Grid,
@@ -35,7 +46,7 @@ fn setup(mut commands: Commands) {
                 ScoreLabel,
                 TextBundle {
                     text: Text::from_section(
-                        "0   0",
+                        "P1  0   0  CPU",
                         TextStyle {
                             font_size: 48.0,
                             ..default()
@@ -45,20 +56,187 @@ fn setup(mut commands: Commands) {
                 },
             ));
         });
+
+    commands
+        .spawn((
+            Name::new("Serve Root"),
+            scope,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            c.spawn((
+                Name::new("lblServe"),
+                ServeLabel,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 64.0,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+
+    commands
+        .spawn((
+            Name::new("Effects Root"),
+            scope,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    width: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|c| {
+            for side in Side::ALL {
+                c.spawn((
+                    Name::new("lblEffects"),
+                    EffectsLabel(side),
+                    TextBundle::default(),
+                ));
+            }
+        });
+}
+
+/// A line of colored names and seconds left for the power-ups running on
+/// each side.
+fn update_effects(simulation: Res<Simulation>, mut labels: Query<(&EffectsLabel, &mut Text)>) {
+    for (label, mut text) in &mut labels {
+        let sections: Vec<_> = simulation
+            .sim
+            .effects
+            .iter()
+            .filter(|effect| effect.side == label.0)
+            .map(effect_section)
+            .collect();
+
+        let changed = text.sections.len() != sections.len()
+            || text
+                .sections
+                .iter()
+                .zip(&sections)
+                .any(|(a, b)| a.value != b.value);
+        if changed {
+            text.sections = sections;
+        }
+    }
+}
+
+fn effect_section(effect: &Effect) -> TextSection {
+    TextSection::new(
+        format!("{} {}  ", effect.kind.name(), effect.remaining.ceil()),
+        TextStyle {
+            font_size: 24.0,
+            color: effect.kind.color(),
+            ..default()
+        },
+    )
+}
+
+/// 3-2-1 before a serve, then a prompt while a manual serve is awaited.
+fn update_serve_countdown(
+    simulation: Res<Simulation>,
+    app_state: Res<State<AppState>>,
+    mut labels: Query<&mut Text, With<ServeLabel>>,
+) {
+    let playing = matches!(app_state.get(), AppState::Game | AppState::Replay);
+
+    let value = match &simulation.sim.serve {
+        _ if !playing => String::new(),
+        Some(serve) if serve.countdown > 0.0 => format!("{}", serve.countdown.ceil()),
+        Some(_) if simulation.sim.rules.manual_serve => "Serve!".to_string(),
+        _ => String::new(),
+    };
+
+    for mut text in &mut labels {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
 }
 
 fn update_score(
     mut query: Query<&mut Text, With<ScoreLabel>>,
-    player_score: Query<&Score, With<Player>>,
-    ai_score: Query<&Score, With<AI>>,
+    simulation: Res<Simulation>,
+    score: Res<MatchScore>,
+    paddles: Query<(&Side, Has<AI>), With<Paddle>>,
 ) {
+    let show_sets = simulation.sim.rules.sets > 1;
+
+    let labels = Side::ALL.map(|side| {
+        let name = team_label(side, paddles.iter());
+
+        let points = if show_sets {
+            format!("{} ({})", score.points(side), score.sets(side))
+        } else {
+            score.points(side).to_string()
+        };
+
+        match side {
+            Side::Left => format!("{name}  {points}"),
+            Side::Right => format!("{points}  {name}"),
+        }
+    });
+
+    let value = match simulation.sim.rules.time_limit {
+        Some(limit) => {
+            let left = (limit - simulation.sim.clock).max(0.0).ceil() as u32;
+            format!(
+                "{}   {}:{:02}   {}",
+                labels[0],
+                left / 60,
+                left % 60,
+                labels[1]
+            )
+        }
+        None => format!("{}   {}", labels[0], labels[1]),
+    };
+
     for mut text in &mut query {
-        let player_score = player_score.single().value;
-        let ai_score = ai_score.single().value;
+        text.sections[0].value = value.clone();
+    }
+}
 
-        text.sections[0].value = format!("{}   {}", player_score, ai_score);
+/// Short name shown for the team on `side`, given every paddle and whether
+/// the AI plays it: the player's if anyone on the team is human.
+pub fn team_label<'a>(side: Side, paddles: impl Iterator<Item = (&'a Side, bool)>) -> &'static str {
+    let is_ai = paddles
+        .filter(|(team, _)| **team == side)
+        .all(|(_, is_ai)| is_ai);
+    paddle_label(side, is_ai)
+}
+
+/// Short name shown for whoever controls the paddle on `side`.
+fn paddle_label(side: Side, is_ai: bool) -> &'static str {
+    match (side, is_ai) {
+        (_, true) => "CPU",
+        (Side::Left, false) => "P1",
+        (Side::Right, false) => "P2",
     }
 }
 
 #[derive(Component)]
 pub struct ScoreLabel;
+
+#[derive(Component)]
+struct ServeLabel;
+
+/// Power-ups running on a side.
+#[derive(Component)]
+struct EffectsLabel(Side);